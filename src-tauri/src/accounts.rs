@@ -0,0 +1,390 @@
+use crate::error::NidaleeError;
+use crate::logging;
+use crate::store::Store;
+use crate::vault::{self, VaultKey};
+use crate::Account;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::info;
+use zeroize::Zeroize;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VaultStatus {
+    pub locked: bool,
+    pub password_protected: bool,
+}
+
+// Every credential the app holds is known to the log redactor before it can be logged.
+pub fn register_secrets<'a>(accounts: impl IntoIterator<Item = &'a Account>) {
+    for account in accounts {
+        logging::REDACTOR.register(&account.username, &account.password);
+    }
+}
+
+fn wipe_accounts(accounts: &mut HashMap<String, Account>) {
+    for account in accounts.values_mut() {
+        account.password.zeroize();
+    }
+    accounts.clear();
+}
+
+// The vault key and the accounts it decrypted. Locks are taken in the order
+// key, accounts, store.
+pub struct AccountVault {
+    key: Mutex<Option<VaultKey>>,
+    accounts: Mutex<HashMap<String, Account>>,
+    // Set when the vault could not be opened at startup; it then stays locked.
+    error: Option<String>,
+}
+
+impl AccountVault {
+    pub fn new(
+        key: Option<VaultKey>,
+        accounts: HashMap<String, Account>,
+        error: Option<String>,
+    ) -> Self {
+        register_secrets(accounts.values());
+        AccountVault {
+            key: Mutex::new(key),
+            accounts: Mutex::new(accounts),
+            error,
+        }
+    }
+
+    fn unlocked_key<'a>(&self, key: &'a Option<VaultKey>) -> Result<&'a VaultKey, NidaleeError> {
+        match (key, &self.error) {
+            (Some(key), _) => Ok(key),
+            (None, Some(e)) => Err(NidaleeError::VaultUnavailable(e.clone())),
+            (None, None) => Err(NidaleeError::VaultLocked),
+        }
+    }
+
+    pub fn account(&self, id: &str) -> Result<Account, NidaleeError> {
+        let key = self.key.lock().unwrap();
+        self.unlocked_key(&key)?;
+        let accounts = self.accounts.lock().unwrap();
+        accounts
+            .get(id)
+            .cloned()
+            .ok_or_else(|| NidaleeError::UnknownAccount(id.to_string()))
+    }
+
+    pub fn status(&self) -> VaultStatus {
+        let key = self.key.lock().unwrap();
+        VaultStatus {
+            locked: key.is_none(),
+            password_protected: match key.as_ref() {
+                Some(key) => key.is_password_protected(),
+                None => true,
+            },
+        }
+    }
+
+    pub fn accounts(&self) -> Result<Vec<Account>, NidaleeError> {
+        let key = self.key.lock().unwrap();
+        self.unlocked_key(&key)?;
+        let accounts = self.accounts.lock().unwrap();
+        Ok(accounts.values().cloned().collect())
+    }
+
+    pub fn save(
+        &self,
+        store: &Mutex<Box<dyn Store>>,
+        mut account: Account,
+    ) -> Result<(), NidaleeError> {
+        let key = self.key.lock().unwrap();
+        let key = self.unlocked_key(&key)?;
+        let mut accounts = self.accounts.lock().unwrap();
+        if let Some(existing) = accounts.get(&account.id) {
+            account.login_count = account.login_count.max(existing.login_count);
+        }
+
+        register_secrets([&account]);
+        store.lock().unwrap().save_account(key, &account)?;
+        accounts.insert(account.id.clone(), account);
+        Ok(())
+    }
+
+    pub fn delete(&self, store: &Mutex<Box<dyn Store>>, id: &str) -> Result<(), NidaleeError> {
+        let key = self.key.lock().unwrap();
+        self.unlocked_key(&key)?;
+        let mut accounts = self.accounts.lock().unwrap();
+
+        store.lock().unwrap().delete_account(id)?;
+        accounts.remove(id);
+        Ok(())
+    }
+
+    // Returns the launch timestamp, which is also written to the cached account.
+    pub fn record_launch(
+        &self,
+        store: &Mutex<Box<dyn Store>>,
+        account_id: &str,
+        game: &str,
+    ) -> Result<String, NidaleeError> {
+        let key = self.key.lock().unwrap();
+        self.unlocked_key(&key)?;
+        let mut accounts = self.accounts.lock().unwrap();
+        let launched_at = store
+            .lock()
+            .unwrap()
+            .record_launch(account_id, game, true)?;
+        if let Some(account) = accounts.get_mut(account_id) {
+            account.last_login = Some(launched_at.clone());
+            account.login_count += 1;
+        }
+        Ok(launched_at)
+    }
+
+    pub fn unlock(
+        &self,
+        store: &Mutex<Box<dyn Store>>,
+        password: &str,
+    ) -> Result<(), NidaleeError> {
+        let mut key = self.key.lock().unwrap();
+        if key.is_some() {
+            return Ok(());
+        }
+        if let Some(e) = &self.error {
+            return Err(NidaleeError::VaultUnavailable(e.clone()));
+        }
+
+        let (unlocked_key, unlocked) = {
+            let mut store = store.lock().unwrap();
+            let unlocked_key = store.unlock(password)?;
+            let unlocked = store.load_accounts(&unlocked_key)?;
+            (unlocked_key, unlocked)
+        };
+
+        register_secrets(unlocked.values());
+        *self.accounts.lock().unwrap() = unlocked;
+        *key = Some(unlocked_key);
+        info!("Vault unlocked");
+        Ok(())
+    }
+
+    pub fn check_lockable(&self) -> Result<(), NidaleeError> {
+        match self.key.lock().unwrap().as_ref() {
+            Some(key) if !key.is_password_protected() => Err(NidaleeError::NoMasterPassword),
+            _ => Ok(()),
+        }
+    }
+
+    // Only a password-protected vault can be locked. Returns whether it was.
+    pub fn lock(&self) -> bool {
+        let mut key = self.key.lock().unwrap();
+        match key.as_ref() {
+            Some(key) if key.is_password_protected() => {}
+            _ => return false,
+        }
+
+        wipe_accounts(&mut self.accounts.lock().unwrap());
+        *key = None;
+        info!("Vault locked");
+        true
+    }
+
+    pub fn change_password(
+        &self,
+        store: &Mutex<Box<dyn Store>>,
+        current_password: Option<&str>,
+        new_password: &str,
+    ) -> Result<(), NidaleeError> {
+        let mut key = self.key.lock().unwrap();
+        let old_key = self.unlocked_key(&key)?;
+        let mut store = store.lock().unwrap();
+
+        if old_key.is_password_protected() {
+            let current_password = current_password.ok_or(NidaleeError::PasswordRequired)?;
+            store
+                .unlock(current_password)
+                .map_err(|_| NidaleeError::WrongPassword)?;
+        }
+
+        let new_key = vault::new_password_key(new_password)?;
+        store.rekey(old_key, &new_key)?;
+        *key = Some(new_key);
+        info!("Master password changed, vault re-encrypted");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{AccountStore, MemoryStore};
+
+    fn account(id: &str, category: &str) -> Account {
+        Account {
+            id: id.to_string(),
+            name: format!("Account {}", id),
+            username: format!("user{}", id),
+            password: format!("password{}", id),
+            email: None,
+            category: category.to_string(),
+            last_login: None,
+            login_count: 0,
+            game_type: "league".to_string(),
+            patchline: None,
+        }
+    }
+
+    // A keyfile vault as it is after startup.
+    fn open_vault() -> (AccountVault, Mutex<Box<dyn Store>>) {
+        let key = VaultKey::generate();
+        let mut store = MemoryStore::new();
+        store.attach_key(&key).unwrap();
+        (
+            AccountVault::new(Some(key), HashMap::new(), None),
+            Mutex::new(Box::new(store)),
+        )
+    }
+
+    fn ids(vault: &AccountVault) -> Vec<String> {
+        let mut ids: Vec<String> = vault
+            .accounts()
+            .unwrap()
+            .into_iter()
+            .map(|a| a.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn saves_and_deletes_accounts() {
+        let (vault, store) = open_vault();
+        vault.save(&store, account("1", "Ranked")).unwrap();
+        vault.save(&store, account("2", "")).unwrap();
+        assert_eq!(ids(&vault), ["1", "2"]);
+        assert_eq!(store.lock().unwrap().load_categories().unwrap(), ["Ranked"]);
+
+        vault.delete(&store, "1").unwrap();
+        assert_eq!(ids(&vault), ["2"]);
+        let key = vault.key.lock().unwrap();
+        let stored = store
+            .lock()
+            .unwrap()
+            .load_accounts(key.as_ref().unwrap())
+            .unwrap();
+        assert!(!stored.contains_key("1"));
+    }
+
+    #[test]
+    fn looks_up_one_account() {
+        let (vault, store) = open_vault();
+        vault.save(&store, account("1", "")).unwrap();
+        assert_eq!(vault.account("1").unwrap().username, "user1");
+        assert_eq!(
+            vault.account("2").unwrap_err(),
+            NidaleeError::UnknownAccount("2".to_string())
+        );
+    }
+
+    #[test]
+    fn saving_keeps_the_launch_count() {
+        let (vault, store) = open_vault();
+        vault.save(&store, account("1", "")).unwrap();
+        vault.record_launch(&store, "1", "league").unwrap();
+        let launched_at = vault.record_launch(&store, "1", "league").unwrap();
+        let launched = vault.accounts().unwrap().remove(0);
+        assert_eq!(launched.last_login, Some(launched_at));
+
+        // The frontend sends back the count it loaded, which may be stale.
+        vault.save(&store, account("1", "")).unwrap();
+        let saved = vault.accounts().unwrap().remove(0);
+        assert_eq!(saved.login_count, 2);
+    }
+
+    #[test]
+    fn keyfile_vault_cannot_be_locked() {
+        let (vault, store) = open_vault();
+        vault.save(&store, account("1", "")).unwrap();
+
+        assert_eq!(vault.check_lockable(), Err(NidaleeError::NoMasterPassword));
+        assert!(!vault.lock());
+        assert_eq!(ids(&vault), ["1"]);
+        assert_eq!(
+            vault.status(),
+            VaultStatus {
+                locked: false,
+                password_protected: false
+            }
+        );
+    }
+
+    #[test]
+    fn locked_vault_refuses_every_command() {
+        let store: Mutex<Box<dyn Store>> = Mutex::new(Box::new(MemoryStore::new()));
+        let vault = AccountVault::new(None, HashMap::new(), None);
+
+        assert_eq!(vault.accounts().unwrap_err(), NidaleeError::VaultLocked);
+        assert_eq!(
+            vault.save(&store, account("1", "")).unwrap_err(),
+            NidaleeError::VaultLocked
+        );
+        assert_eq!(
+            vault.delete(&store, "1").unwrap_err(),
+            NidaleeError::VaultLocked
+        );
+        assert_eq!(
+            vault.change_password(&store, None, "new").unwrap_err(),
+            NidaleeError::VaultLocked
+        );
+        assert_eq!(vault.account("1").unwrap_err(), NidaleeError::VaultLocked);
+    }
+
+    #[test]
+    fn unavailable_vault_reports_why() {
+        let store: Mutex<Box<dyn Store>> = Mutex::new(Box::new(MemoryStore::new()));
+        let error = "Invalid vault key length".to_string();
+        let vault = AccountVault::new(None, HashMap::new(), Some(error.clone()));
+
+        let unavailable = NidaleeError::VaultUnavailable(error);
+        assert_eq!(vault.accounts().unwrap_err(), unavailable);
+        assert_eq!(
+            vault.save(&store, account("1", "")).unwrap_err(),
+            unavailable
+        );
+        assert_eq!(vault.unlock(&store, "password").unwrap_err(), unavailable);
+    }
+
+    #[test]
+    fn master_password_locks_and_unlocks() {
+        let (vault, store) = open_vault();
+        vault.save(&store, account("1", "")).unwrap();
+
+        assert_eq!(
+            vault.change_password(&store, None, "").unwrap_err().code(),
+            "invalid_input"
+        );
+        vault.change_password(&store, None, "first").unwrap();
+        assert_eq!(
+            vault.change_password(&store, None, "second"),
+            Err(NidaleeError::PasswordRequired)
+        );
+        assert_eq!(
+            vault.change_password(&store, Some("wrong"), "second"),
+            Err(NidaleeError::WrongPassword)
+        );
+
+        assert!(vault.lock());
+        assert!(vault.status().locked);
+        assert_eq!(vault.accounts().unwrap_err(), NidaleeError::VaultLocked);
+
+        assert_eq!(
+            vault.unlock(&store, "wrong"),
+            Err(NidaleeError::WrongPassword)
+        );
+        vault.unlock(&store, "first").unwrap();
+        assert_eq!(ids(&vault), ["1"]);
+        assert_eq!(
+            vault.status(),
+            VaultStatus {
+                locked: false,
+                password_protected: true
+            }
+        );
+    }
+}
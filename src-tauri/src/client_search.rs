@@ -0,0 +1,308 @@
+use crate::idle::Clock;
+use crate::launch::CancelToken;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+
+pub const CLIENT_EXECUTABLE: &str = "RiotClientServices.exe";
+
+// Large folders that never hold the client.
+const SKIP_DIRS: &[&str] = &[
+    "League of Legends",
+    "VALORANT",
+    "LoR",
+    "Logs",
+    "node_modules",
+    "$Recycle.Bin",
+    "System Volume Information",
+];
+
+// Progress is reported after this many folders, besides once per root.
+const PROGRESS_EVERY: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchLimits {
+    // Folders below a root that are still opened; the root itself is depth 0.
+    pub max_depth: usize,
+    // Wall-clock budget for the whole search, across all roots.
+    pub budget: Duration,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        SearchLimits {
+            max_depth: 4,
+            budget: Duration::from_secs(15),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchProgress {
+    pub root: String,
+    pub roots_done: usize,
+    pub roots_total: usize,
+    pub dirs_scanned: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchOutcome {
+    Found(PathBuf),
+    NotFound,
+    OutOfTime,
+    Cancelled,
+}
+
+fn is_skipped(name: &str) -> bool {
+    SKIP_DIRS.iter().any(|skip| skip.eq_ignore_ascii_case(name))
+}
+
+// Breadth-first over each root in turn, so a shallow match in an early root wins.
+// Symlinks and junctions are not followed, which also keeps the walk out of loops.
+pub fn search(
+    roots: &[PathBuf],
+    target: &str,
+    limits: &SearchLimits,
+    clock: &dyn Clock,
+    cancel: &CancelToken,
+    progress: &mut dyn FnMut(&SearchProgress),
+) -> SearchOutcome {
+    let deadline = clock.now() + limits.budget;
+    let mut dirs_scanned = 0;
+
+    for (index, root) in roots.iter().enumerate() {
+        let report = |dirs_scanned| SearchProgress {
+            root: root.to_string_lossy().into_owned(),
+            roots_done: index,
+            roots_total: roots.len(),
+            dirs_scanned,
+        };
+        progress(&report(dirs_scanned));
+        if !root.is_dir() {
+            continue;
+        }
+
+        let mut queue = VecDeque::from([(root.clone(), 0)]);
+        while let Some((dir, depth)) = queue.pop_front() {
+            if cancel.is_cancelled() {
+                return SearchOutcome::Cancelled;
+            }
+            if clock.now() >= deadline {
+                warn!("Riot Client search ran out of time in {}", root.display());
+                return SearchOutcome::OutOfTime;
+            }
+
+            dirs_scanned += 1;
+            if dirs_scanned % PROGRESS_EVERY == 0 {
+                progress(&report(dirs_scanned));
+            }
+            let descend = depth < limits.max_depth;
+            if let Some(found) = scan_dir(&dir, depth, descend, target, &mut queue) {
+                return SearchOutcome::Found(found);
+            }
+        }
+    }
+    SearchOutcome::NotFound
+}
+
+fn scan_dir(
+    dir: &Path,
+    depth: usize,
+    descend: bool,
+    target: &str,
+    queue: &mut VecDeque<(PathBuf, usize)>,
+) -> Option<PathBuf> {
+    let entries = fs::read_dir(dir).ok()?;
+    for entry in entries.filter_map(Result::ok) {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if file_type.is_file() && name.eq_ignore_ascii_case(target) {
+            return Some(entry.path());
+        }
+        if descend && file_type.is_dir() && !is_skipped(&name) {
+            queue.push_back((entry.path(), depth + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idle::FakeClock;
+    use std::time::Instant;
+
+    // Every look at the clock costs this much time, like a slow disk would.
+    struct SlowDisk {
+        clock: FakeClock,
+        per_look: Duration,
+    }
+
+    impl Clock for SlowDisk {
+        fn now(&self) -> Instant {
+            self.clock.advance(self.per_look);
+            self.clock.now()
+        }
+    }
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"MZ").unwrap();
+    }
+
+    fn find(roots: &[PathBuf], limits: &SearchLimits) -> SearchOutcome {
+        search(
+            roots,
+            CLIENT_EXECUTABLE,
+            limits,
+            &FakeClock::new(),
+            &CancelToken::default(),
+            &mut |_| {},
+        )
+    }
+
+    #[test]
+    fn finds_the_client_down_to_the_depth_limit() {
+        let root = tempfile::tempdir().unwrap();
+        let deepest = root
+            .path()
+            .join("Games")
+            .join("Riot")
+            .join("Riot Games")
+            .join("Riot Client");
+        let client = deepest.join(CLIENT_EXECUTABLE);
+        touch(&client);
+
+        let roots = [root.path().to_path_buf()];
+        assert_eq!(
+            find(&roots, &SearchLimits::default()),
+            SearchOutcome::Found(client)
+        );
+
+        let shallow = SearchLimits {
+            max_depth: 3,
+            ..SearchLimits::default()
+        };
+        assert_eq!(find(&roots, &shallow), SearchOutcome::NotFound);
+    }
+
+    #[test]
+    fn skips_game_and_system_folders() {
+        let root = tempfile::tempdir().unwrap();
+        touch(
+            &root
+                .path()
+                .join("League of Legends")
+                .join(CLIENT_EXECUTABLE),
+        );
+        touch(
+            &root
+                .path()
+                .join("Node_Modules")
+                .join("riot")
+                .join(CLIENT_EXECUTABLE),
+        );
+
+        assert_eq!(
+            find(&[root.path().to_path_buf()], &SearchLimits::default()),
+            SearchOutcome::NotFound
+        );
+    }
+
+    #[test]
+    fn earlier_roots_and_shallower_folders_win() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        touch(&first.path().join("a").join("b").join(CLIENT_EXECUTABLE));
+        let shallow = first
+            .path()
+            .join("Riot Client")
+            .join("riotclientservices.exe");
+        touch(&shallow);
+        touch(&second.path().join(CLIENT_EXECUTABLE));
+
+        let missing = first.path().join("missing");
+        let roots = [
+            missing,
+            first.path().to_path_buf(),
+            second.path().to_path_buf(),
+        ];
+        let mut reports = Vec::new();
+        let outcome = search(
+            &roots,
+            CLIENT_EXECUTABLE,
+            &SearchLimits::default(),
+            &FakeClock::new(),
+            &CancelToken::default(),
+            &mut |progress| reports.push((progress.roots_done, progress.roots_total)),
+        );
+
+        assert_eq!(outcome, SearchOutcome::Found(shallow));
+        assert_eq!(reports, [(0, 3), (1, 3)]);
+    }
+
+    #[test]
+    fn stops_when_the_budget_runs_out() {
+        let root = tempfile::tempdir().unwrap();
+        let mut dir = root.path().to_path_buf();
+        for name in ["a", "b", "c", "d"] {
+            dir.push(name);
+        }
+        let client = dir.join(CLIENT_EXECUTABLE);
+        touch(&client);
+
+        let roots = [root.path().to_path_buf()];
+        let search_with = |budget| {
+            let clock = SlowDisk {
+                clock: FakeClock::new(),
+                per_look: Duration::from_millis(300),
+            };
+            let limits = SearchLimits {
+                max_depth: 4,
+                budget,
+            };
+            search(
+                &roots,
+                CLIENT_EXECUTABLE,
+                &limits,
+                &clock,
+                &CancelToken::default(),
+                &mut |_| {},
+            )
+        };
+
+        // Reaching the client means opening five folders, and a second only covers three.
+        assert_eq!(
+            search_with(Duration::from_secs(1)),
+            SearchOutcome::OutOfTime
+        );
+        assert_eq!(
+            search_with(Duration::from_secs(10)),
+            SearchOutcome::Found(client)
+        );
+    }
+
+    #[test]
+    fn stops_when_cancelled() {
+        let root = tempfile::tempdir().unwrap();
+        touch(&root.path().join("Riot Client").join(CLIENT_EXECUTABLE));
+
+        let cancel = CancelToken::default();
+        let canceller = cancel.clone();
+        let outcome = search(
+            &[root.path().to_path_buf()],
+            CLIENT_EXECUTABLE,
+            &SearchLimits::default(),
+            &FakeClock::new(),
+            &cancel,
+            &mut |_| canceller.cancel(),
+        );
+        assert_eq!(outcome, SearchOutcome::Cancelled);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::platform::{Clipboard, ClipboardSnapshot, FocusGuard, InputKey, Platform};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::warn;
+
+const CHAR_DELAY: Duration = Duration::from_millis(5);
+const FIELD_DELAY: Duration = Duration::from_millis(100);
+const PASTE_DELAY: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryMode {
+    #[default]
+    Typing,
+    Clipboard,
+}
+
+pub trait CredentialEntry {
+    // `pause` sleeps between steps and fails once the launch has been cancelled.
+    fn enter(
+        &self,
+        input: &FocusGuard,
+        text: &str,
+        pause: &dyn Fn(Duration) -> Result<(), String>,
+    ) -> Result<(), String>;
+}
+
+pub fn strategy(mode: EntryMode, platform: &Platform) -> Box<dyn CredentialEntry + '_> {
+    match mode {
+        EntryMode::Typing => Box::new(TypingEntry),
+        EntryMode::Clipboard => Box::new(ClipboardEntry::new(platform.clipboard.as_ref())),
+    }
+}
+
+// Sends one character at a time. Works everywhere the text can be typed on the
+// current keyboard layout, which rules out some non-ASCII passwords.
+pub struct TypingEntry;
+
+impl CredentialEntry for TypingEntry {
+    fn enter(
+        &self,
+        input: &FocusGuard,
+        text: &str,
+        pause: &dyn Fn(Duration) -> Result<(), String>,
+    ) -> Result<(), String> {
+        for c in text.chars() {
+            input.type_text(&c.to_string())?;
+            pause(CHAR_DELAY)?;
+        }
+        pause(FIELD_DELAY)
+    }
+}
+
+// Pastes the text instead of typing it, so the keyboard layout doesn't matter.
+// Whatever was on the clipboard before is put back afterwards in every format,
+// even on failure.
+pub struct ClipboardEntry<'a> {
+    clipboard: &'a dyn Clipboard,
+}
+
+impl<'a> ClipboardEntry<'a> {
+    pub fn new(clipboard: &'a dyn Clipboard) -> Self {
+        ClipboardEntry { clipboard }
+    }
+}
+
+struct RestoreClipboard<'a> {
+    clipboard: &'a dyn Clipboard,
+    previous: ClipboardSnapshot,
+}
+
+impl Drop for RestoreClipboard<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.clipboard.restore(&self.previous) {
+            warn!("Failed to restore clipboard: {}", e);
+        }
+    }
+}
+
+impl CredentialEntry for ClipboardEntry<'_> {
+    fn enter(
+        &self,
+        input: &FocusGuard,
+        text: &str,
+        pause: &dyn Fn(Duration) -> Result<(), String>,
+    ) -> Result<(), String> {
+        // Without a copy of the old contents they could not be put back, so the
+        // clipboard is left alone.
+        let previous = self
+            .clipboard
+            .save()
+            .map_err(|e| format!("Could not read the clipboard: {}", e))?;
+        let _restore = RestoreClipboard {
+            clipboard: self.clipboard,
+            previous,
+        };
+        self.clipboard.set_private_text(text)?;
+        pause(PASTE_DELAY)?;
+        input.key(InputKey::Paste)?;
+        // The target reads the clipboard asynchronously, so wait before restoring it.
+        pause(FIELD_DELAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::fake::{Action, FakePlatform, FakeState, FakeWindow, TEXT_FORMAT};
+    use crate::platform::{WindowHandle, WindowRect, FOCUS_LOST};
+
+    const CLIENT: WindowHandle = WindowHandle(1);
+    // CF_DIB and a registered HTML format, as left behind by copying from a browser.
+    const IMAGE: (u32, &[u8]) = (8, &[40, 0, 0, 0, 1, 0, 0, 0]);
+    const HTML: (u32, &[u8]) = (49_350, b"<img src=\"cat.png\">");
+
+    fn client(state: FakeState) -> FakePlatform {
+        FakePlatform::new(FakeState {
+            windows: vec![FakeWindow {
+                handle: CLIENT,
+                class: "Chrome_WidgetWin_1".to_string(),
+                title: "Riot Client".to_string(),
+                rect: WindowRect::default(),
+                pid: 10,
+                minimized: false,
+            }],
+            foreground: Some(CLIENT),
+            ..state
+        })
+    }
+
+    fn copied_image() -> ClipboardSnapshot {
+        ClipboardSnapshot {
+            formats: vec![(IMAGE.0, IMAGE.1.to_vec()), (HTML.0, HTML.1.to_vec())],
+        }
+    }
+
+    fn paste(fake: &FakePlatform, text: &str) -> Result<(), String> {
+        let guard = FocusGuard::new(fake, fake, CLIENT);
+        ClipboardEntry::new(fake).enter(&guard, text, &|_| Ok(()))
+    }
+
+    #[test]
+    fn types_one_character_at_a_time() {
+        let fake = client(FakeState::default());
+        let guard = FocusGuard::new(&fake, &fake, CLIENT);
+        TypingEntry.enter(&guard, "ab", &|_| Ok(())).unwrap();
+        assert_eq!(
+            fake.actions(),
+            [Action::Type("a".to_string()), Action::Type("b".to_string())]
+        );
+    }
+
+    #[test]
+    fn puts_back_every_format_after_pasting() {
+        let fake = client(FakeState {
+            clipboard: copied_image(),
+            ..FakeState::default()
+        });
+        paste(&fake, "hunter2").unwrap();
+
+        assert_eq!(
+            fake.actions(),
+            [
+                Action::SetClipboard("hunter2".to_string()),
+                Action::Key(InputKey::Paste),
+                Action::RestoreClipboard(copied_image()),
+            ]
+        );
+        assert_eq!(fake.state.lock().unwrap().clipboard, copied_image());
+    }
+
+    #[test]
+    fn restores_text_and_empty_clipboards() {
+        let text = ClipboardSnapshot {
+            formats: vec![(TEXT_FORMAT, b"notes".to_vec())],
+        };
+        for previous in [text, ClipboardSnapshot::default()] {
+            let fake = client(FakeState {
+                clipboard: previous.clone(),
+                ..FakeState::default()
+            });
+            paste(&fake, "hunter2").unwrap();
+            assert_eq!(fake.state.lock().unwrap().clipboard, previous);
+        }
+    }
+
+    #[test]
+    fn restores_the_clipboard_when_the_paste_fails() {
+        let fake = client(FakeState {
+            clipboard: copied_image(),
+            ..FakeState::default()
+        });
+        fake.state.lock().unwrap().foreground = None;
+
+        assert_eq!(paste(&fake, "hunter2"), Err(FOCUS_LOST.to_string()));
+        assert_eq!(fake.state.lock().unwrap().clipboard, copied_image());
+    }
+
+    #[test]
+    fn leaves_a_clipboard_it_cannot_read_alone() {
+        let fake = client(FakeState {
+            clipboard: copied_image(),
+            clipboard_busy: true,
+            ..FakeState::default()
+        });
+
+        assert!(paste(&fake, "hunter2").is_err());
+        assert!(fake.actions().is_empty());
+        assert_eq!(fake.state.lock().unwrap().clipboard, copied_image());
+    }
+}
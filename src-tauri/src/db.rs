@@ -0,0 +1,1043 @@
+use crate::error::NidaleeError;
+use crate::migrations;
+use crate::storage;
+use crate::vault::{self, KdfParams, VaultKey};
+use crate::{Account, Settings};
+use chrono::Utc;
+use rusqlite::{params, Connection, ErrorCode, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+use zeroize::Zeroize;
+
+pub const DATABASE_FILE: &str = "nidalee.db";
+
+// SCHEMA[n] upgrades the database from user_version n to n + 1.
+const SCHEMA: &[&str] = &[
+    "
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE categories (
+        name TEXT PRIMARY KEY,
+        position INTEGER NOT NULL
+    );
+    CREATE TABLE vault (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        kdf TEXT,
+        check_value BLOB,
+        pending_import TEXT
+    );
+    CREATE TABLE accounts (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        credentials BLOB NOT NULL,
+        email TEXT,
+        category TEXT REFERENCES categories(name) ON UPDATE CASCADE ON DELETE SET NULL,
+        last_login TEXT,
+        login_count INTEGER NOT NULL DEFAULT 0,
+        game_type TEXT NOT NULL
+    );
+    CREATE TABLE launch_history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        account_id TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+        game TEXT NOT NULL,
+        launched_at TEXT NOT NULL,
+        success INTEGER NOT NULL
+    );
+    CREATE INDEX launch_history_account ON launch_history(account_id);
+    ",
+    "ALTER TABLE accounts ADD COLUMN patchline TEXT;",
+];
+
+#[derive(Serialize, Deserialize)]
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl Drop for Credentials {
+    fn drop(&mut self) {
+        self.username.zeroize();
+        self.password.zeroize();
+    }
+}
+
+pub struct VaultRecord {
+    pub kdf: Option<KdfParams>,
+    check_value: Option<Vec<u8>>,
+    pending_import: Option<String>,
+}
+
+// Only a database that is corrupt is set aside; any other failure is returned as is
+// and leaves the file untouched.
+enum OpenError {
+    Corrupt(String),
+    Failed(NidaleeError),
+}
+
+impl From<rusqlite::Error> for OpenError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e.sqlite_error_code() {
+            Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) => {
+                OpenError::Corrupt(e.to_string())
+            }
+            _ => OpenError::Failed(e.into()),
+        }
+    }
+}
+
+impl From<OpenError> for NidaleeError {
+    fn from(e: OpenError) -> Self {
+        match e {
+            OpenError::Corrupt(e) => NidaleeError::Io(format!("database is corrupt: {}", e)),
+            OpenError::Failed(e) => e,
+        }
+    }
+}
+
+pub fn open(path: &Path) -> Result<Connection, NidaleeError> {
+    match open_checked(path) {
+        Ok(conn) => {
+            if let Err(e) = snapshot(&conn, path) {
+                warn!("Failed to back up database: {}", e);
+            }
+            Ok(conn)
+        }
+        Err(OpenError::Corrupt(e)) => {
+            warn!(
+                "Database {} is corrupt ({}), trying backups...",
+                path.display(),
+                e
+            );
+            restore_from_backup(path)
+        }
+        Err(OpenError::Failed(e)) => Err(e),
+    }
+}
+
+pub fn open_read_only(path: &Path) -> Result<Connection, NidaleeError> {
+    Ok(Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?)
+}
+
+fn open_checked(path: &Path) -> Result<Connection, OpenError> {
+    let mut conn = Connection::open(path)?;
+    conn.pragma_update(None, "foreign_keys", true)?;
+
+    let check: String = conn.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+    if check != "ok" {
+        return Err(OpenError::Corrupt(format!("integrity check failed: {}", check)));
+    }
+
+    migrate(&mut conn)?;
+    Ok(conn)
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), OpenError> {
+    let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version as usize > SCHEMA.len() {
+        return Err(OpenError::Failed(NidaleeError::DatabaseTooNew {
+            version,
+            supported: SCHEMA.len() as u32,
+        }));
+    }
+
+    for (index, sql) in SCHEMA.iter().enumerate().skip(version as usize) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", (index + 1) as u32)?;
+        tx.commit()?;
+        info!("Database migrated to schema version {}", index + 1);
+    }
+    Ok(())
+}
+
+// The restored or recreated database is not snapshotted, so a failed restore never
+// replaces a backup with an empty database.
+fn restore_from_backup(path: &Path) -> Result<Connection, NidaleeError> {
+    storage::quarantine(path);
+
+    for backup_path in storage::list_backups(path) {
+        if fs::copy(&backup_path, path).is_err() {
+            continue;
+        }
+        match open_checked(path) {
+            Ok(conn) => {
+                info!("Recovered {} from {}", path.display(), backup_path.display());
+                return Ok(conn);
+            }
+            Err(e) => {
+                warn!(
+                    "Backup {} is not usable: {}",
+                    backup_path.display(),
+                    NidaleeError::from(e)
+                );
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    warn!("No valid database backup found, starting with an empty database");
+    Ok(open_checked(path)?)
+}
+
+fn snapshot(conn: &Connection, path: &Path) -> Result<(), NidaleeError> {
+    let backup_path = storage::new_backup_path(path).map_err(NidaleeError::Io)?;
+    conn.execute(
+        "VACUUM INTO ?1",
+        params![backup_path.to_string_lossy().into_owned()],
+    )?;
+    storage::prune_backups(path);
+    Ok(())
+}
+
+fn get_meta(conn: &Connection, key: &str) -> Result<Option<String>, NidaleeError> {
+    Ok(conn
+        .query_row("SELECT value FROM meta WHERE key = ?1", params![key], |row| {
+            row.get(0)
+        })
+        .optional()?)
+}
+
+fn set_meta(conn: &Connection, key: &str, value: &str) -> Result<(), NidaleeError> {
+    conn.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+pub fn load_settings(conn: &Connection) -> Result<Option<Settings>, NidaleeError> {
+    let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut map = Map::new();
+    for row in rows {
+        let (key, value) = row?;
+        map.insert(key, serde_json::from_str(&value)?);
+    }
+    if map.is_empty() {
+        return Ok(None);
+    }
+
+    let (value, upgraded) = migrations::upgrade(&migrations::SETTINGS, Value::Object(map))?;
+    let settings: Settings = serde_json::from_value(value)?;
+    if upgraded {
+        save_settings(conn, &settings)?;
+    }
+    Ok(Some(settings))
+}
+
+pub fn read_settings(path: &Path) -> Option<Settings> {
+    open_read_only(path)
+        .and_then(|conn| load_settings(&conn))
+        .ok()
+        .flatten()
+}
+
+fn write_settings(conn: &Connection, settings: &Settings) -> Result<(), NidaleeError> {
+    let Value::Object(mut map) = serde_json::to_value(settings)? else {
+        return Err(NidaleeError::Internal(
+            "Settings did not serialize to an object".to_string(),
+        ));
+    };
+    map.insert(
+        migrations::SCHEMA_VERSION_FIELD.to_string(),
+        json!(migrations::SETTINGS.version()),
+    );
+
+    let mut stmt = conn
+        .prepare(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        )?;
+    for (key, value) in map {
+        stmt.execute(params![key, value.to_string()])?;
+    }
+    Ok(())
+}
+
+pub fn save_settings(conn: &Connection, settings: &Settings) -> Result<(), NidaleeError> {
+    let tx = conn.unchecked_transaction()?;
+    write_settings(&tx, settings)?;
+    Ok(tx.commit()?)
+}
+
+pub fn load_categories(conn: &Connection) -> Result<Vec<String>, NidaleeError> {
+    let mut stmt = conn.prepare("SELECT name FROM categories ORDER BY position, name")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+fn write_categories(conn: &Connection, categories: &[String]) -> Result<(), NidaleeError> {
+    let mut wanted: Vec<&str> = Vec::new();
+    for name in categories.iter().map(|name| name.trim()) {
+        if !name.is_empty() && !wanted.contains(&name) {
+            wanted.push(name);
+        }
+    }
+
+    for existing in load_categories(conn)? {
+        if !wanted.contains(&existing.as_str()) {
+            conn.execute("DELETE FROM categories WHERE name = ?1", params![existing])?;
+        }
+    }
+
+    for (position, name) in wanted.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO categories (name, position) VALUES (?1, ?2)
+             ON CONFLICT(name) DO UPDATE SET position = excluded.position",
+            params![name, position as i64],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn save_categories(conn: &Connection, categories: &[String]) -> Result<(), NidaleeError> {
+    let tx = conn.unchecked_transaction()?;
+    write_categories(&tx, categories)?;
+    Ok(tx.commit()?)
+}
+
+pub fn vault_record(conn: &Connection) -> Result<Option<VaultRecord>, NidaleeError> {
+    let row = conn
+        .query_row(
+            "SELECT kdf, check_value, pending_import FROM vault WHERE id = 1",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?,
+                    row.get::<_, Option<Vec<u8>>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    let Some((kdf, check_value, pending_import)) = row else {
+        return Ok(None);
+    };
+    let kdf = match kdf {
+        Some(kdf) => Some(serde_json::from_str(&kdf)?),
+        None => None,
+    };
+    Ok(Some(VaultRecord {
+        kdf,
+        check_value,
+        pending_import,
+    }))
+}
+
+fn write_vault(conn: &Connection, key: &VaultKey, pending_import: Option<&str>) -> Result<(), NidaleeError> {
+    let kdf = match key.kdf() {
+        Some(kdf) => Some(serde_json::to_string(kdf)?),
+        None => None,
+    };
+    let check_value = vault::check_value(key)?;
+    conn.execute(
+        "INSERT INTO vault (id, kdf, check_value, pending_import) VALUES (1, ?1, ?2, ?3)
+         ON CONFLICT(id) DO UPDATE SET
+             kdf = excluded.kdf,
+             check_value = excluded.check_value,
+             pending_import = excluded.pending_import",
+        params![kdf, check_value, pending_import],
+    )?;
+    Ok(())
+}
+
+pub fn init_vault(conn: &Connection, key: &VaultKey) -> Result<(), NidaleeError> {
+    write_vault(conn, key, None)
+}
+
+pub fn verify_key(record: &VaultRecord, key: &VaultKey) -> bool {
+    match &record.check_value {
+        Some(check_value) => vault::verify_check(key, check_value),
+        None => false,
+    }
+}
+
+pub fn unlock_with_password(conn: &Connection, password: &str) -> Result<VaultKey, NidaleeError> {
+    let not_protected = || NidaleeError::Internal("Vault is not password protected".to_string());
+    let record = vault_record(conn)?.ok_or_else(not_protected)?;
+    let params = record.kdf.as_ref().ok_or_else(not_protected)?;
+    let key = vault::derive_key(password, params)?;
+
+    if let Some(pending) = &record.pending_import {
+        let tx = conn.unchecked_transaction()?;
+        let (accounts, _) = read_legacy_vault(&key, pending)?;
+        for account in accounts.values() {
+            write_account(&tx, &key, account)?;
+        }
+        write_vault(&tx, &key, None)?;
+        tx.commit()?;
+        info!("Imported {} accounts from the legacy vault", accounts.len());
+    } else if !verify_key(&record, &key) {
+        return Err(NidaleeError::WrongPassword);
+    }
+    Ok(key)
+}
+
+pub fn load_accounts(conn: &Connection, key: &VaultKey) -> Result<HashMap<String, Account>, NidaleeError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, credentials, email, category, last_login, login_count, game_type,
+                    patchline
+             FROM accounts",
+        )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Vec<u8>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, u32>(6)?,
+                row.get::<_, String>(7)?,
+                row.get::<_, Option<String>>(8)?,
+            ))
+        })?;
+
+    let mut accounts = HashMap::new();
+    for row in rows {
+        let (id, name, sealed, email, category, last_login, login_count, game_type, patchline) =
+            row?;
+        let credentials = open_credentials(key, &id, &sealed)?;
+        accounts.insert(
+            id.clone(),
+            Account {
+                id,
+                name,
+                username: credentials.username.clone(),
+                password: credentials.password.clone(),
+                email,
+                category: category.unwrap_or_default(),
+                last_login,
+                login_count,
+                game_type,
+                patchline,
+            },
+        );
+    }
+    Ok(accounts)
+}
+
+fn seal_credentials(key: &VaultKey, account: &Account) -> Result<Vec<u8>, NidaleeError> {
+    let credentials = Credentials {
+        username: account.username.clone(),
+        password: account.password.clone(),
+    };
+    let mut plaintext = serde_json::to_vec(&credentials)?;
+    let sealed = vault::encrypt(key, &plaintext, account.id.as_bytes());
+    plaintext.zeroize();
+    sealed
+}
+
+fn open_credentials(key: &VaultKey, id: &str, sealed: &[u8]) -> Result<Credentials, NidaleeError> {
+    let mut plaintext = vault::decrypt(key, sealed, id.as_bytes())?;
+    let credentials = serde_json::from_slice(&plaintext).map_err(NidaleeError::from);
+    plaintext.zeroize();
+    credentials
+}
+
+fn write_account(conn: &Connection, key: &VaultKey, account: &Account) -> Result<(), NidaleeError> {
+    let category = account.category.trim();
+    let category = if category.is_empty() {
+        None
+    } else {
+        conn.execute(
+            "INSERT OR IGNORE INTO categories (name, position)
+             VALUES (?1, (SELECT COALESCE(MAX(position), -1) + 1 FROM categories))",
+            params![category],
+        )?;
+        Some(category)
+    };
+
+    conn.execute(
+        "INSERT INTO accounts
+             (id, name, credentials, email, category, last_login, login_count, game_type, patchline)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT(id) DO UPDATE SET
+             name = excluded.name,
+             credentials = excluded.credentials,
+             email = excluded.email,
+             category = excluded.category,
+             last_login = excluded.last_login,
+             login_count = excluded.login_count,
+             game_type = excluded.game_type,
+             patchline = excluded.patchline",
+        params![
+            account.id,
+            account.name,
+            seal_credentials(key, account)?,
+            account.email,
+            category,
+            account.last_login,
+            account.login_count,
+            account.game_type,
+            account.patchline,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn save_account(conn: &Connection, key: &VaultKey, account: &Account) -> Result<(), NidaleeError> {
+    let tx = conn.unchecked_transaction()?;
+    write_account(&tx, key, account)?;
+    Ok(tx.commit()?)
+}
+
+pub fn delete_account(conn: &Connection, id: &str) -> Result<(), NidaleeError> {
+    conn.execute("DELETE FROM accounts WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+pub fn record_launch(
+    conn: &Connection,
+    account_id: &str,
+    game: &str,
+    success: bool,
+) -> Result<String, NidaleeError> {
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO launch_history (account_id, game, launched_at, success)
+         SELECT id, ?2, ?3, ?4 FROM accounts WHERE id = ?1",
+        params![account_id, game, now, success],
+    )?;
+    if success {
+        tx.execute(
+            "UPDATE accounts SET last_login = ?2, login_count = login_count + 1 WHERE id = ?1",
+            params![account_id, now],
+        )?;
+    }
+    tx.commit()?;
+    Ok(now)
+}
+
+pub fn rekey(conn: &Connection, old_key: &VaultKey, new_key: &VaultKey) -> Result<(), NidaleeError> {
+    let tx = conn.unchecked_transaction()?;
+    let rows = {
+        let mut stmt = tx.prepare("SELECT id, credentials FROM accounts")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)))?;
+        rows.collect::<Result<Vec<_>, _>>()?
+    };
+
+    for (id, sealed) in rows {
+        let mut plaintext = vault::decrypt(old_key, &sealed, id.as_bytes())?;
+        let resealed = vault::encrypt(new_key, &plaintext, id.as_bytes());
+        plaintext.zeroize();
+        tx.execute(
+            "UPDATE accounts SET credentials = ?2 WHERE id = ?1",
+            params![id, resealed?],
+        )?;
+    }
+
+    write_vault(&tx, new_key, None)?;
+    Ok(tx.commit()?)
+}
+
+#[derive(Deserialize)]
+struct AccountsFile {
+    accounts: HashMap<String, Account>,
+}
+
+#[derive(Deserialize)]
+struct CategoriesFile {
+    categories: Vec<String>,
+}
+
+fn parse_accounts(content: &str) -> Result<(HashMap<String, Account>, bool), NidaleeError> {
+    let (value, upgraded) = migrations::upgrade_str(&migrations::ACCOUNTS, content)?;
+    let file: AccountsFile = serde_json::from_value(value)?;
+    Ok((file.accounts, upgraded))
+}
+
+fn read_legacy_vault(
+    key: &VaultKey,
+    content: &str,
+) -> Result<(HashMap<String, Account>, bool), NidaleeError> {
+    let mut accounts_json = vault::open(key, content)?;
+    let accounts = parse_accounts(&String::from_utf8_lossy(&accounts_json));
+    accounts_json.zeroize();
+    accounts
+}
+
+fn parse_settings(content: &str) -> Result<Settings, NidaleeError> {
+    let (value, _) = migrations::upgrade_str(&migrations::SETTINGS, content)?;
+    Ok(serde_json::from_value(value)?)
+}
+
+fn parse_categories(content: &str) -> Result<Vec<String>, NidaleeError> {
+    let (value, _) = migrations::upgrade_str(&migrations::CATEGORIES, content)?;
+    let file: CategoriesFile = serde_json::from_value(value)?;
+    Ok(file.categories)
+}
+
+// Imports settings.json, categories.json and accounts.json from earlier releases exactly once.
+// A password-protected accounts vault cannot be read until the user unlocks it, so it is kept
+// in the vault row and finished by unlock_with_password.
+pub fn import_legacy_json(conn: &Connection, app_data_dir: &Path) -> Result<(), NidaleeError> {
+    let settings_path = app_data_dir.join("settings.json");
+    let categories_path = app_data_dir.join("categories.json");
+    let accounts_path = app_data_dir.join("accounts.json");
+
+    if get_meta(conn, "json_imported")?.is_some() {
+        remove_plaintext_backups(&accounts_path);
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+
+    if let Some(settings) = storage::load(&settings_path, parse_settings) {
+        write_settings(&tx, &settings)?;
+        info!("Imported settings.json");
+    }
+
+    if let Some(categories) = storage::load(&categories_path, parse_categories) {
+        write_categories(&tx, &categories)?;
+        info!("Imported {} categories", categories.len());
+    }
+
+    let accounts_content = storage::load(&accounts_path, |content| {
+        if vault::is_vault(content) || parse_accounts(content).is_ok() {
+            Ok(content.to_string())
+        } else {
+            Err(NidaleeError::Parse("Unrecognized accounts file".to_string()))
+        }
+    });
+    let mut plaintext_accounts = false;
+    if let Some(content) = accounts_content {
+        let legacy_kdf = if vault::is_vault(&content) {
+            vault::kdf_params(&content)?
+        } else {
+            None
+        };
+
+        if let Some(kdf) = legacy_kdf {
+            tx.execute(
+                "INSERT INTO vault (id, kdf, check_value, pending_import) VALUES (1, ?1, NULL, ?2)",
+                params![serde_json::to_string(&kdf)?, content],
+            )?;
+            info!("Password-protected accounts will be imported on first unlock");
+        } else {
+            let key = vault::load_or_create_key(&app_data_dir.join("vault.key"))?;
+            let (accounts, _) = if vault::is_vault(&content) {
+                read_legacy_vault(&key, &content)?
+            } else {
+                plaintext_accounts = true;
+                parse_accounts(&content)?
+            };
+            write_vault(&tx, &key, None)?;
+            for account in accounts.values() {
+                write_account(&tx, &key, account)?;
+            }
+            info!("Imported {} accounts", accounts.len());
+        }
+    }
+
+    set_meta(&tx, "json_imported", &Utc::now().to_rfc3339())?;
+    tx.commit()?;
+
+    for path in [&settings_path, &categories_path, &accounts_path] {
+        if !path.exists() {
+            continue;
+        }
+        if plaintext_accounts && path == &accounts_path {
+            let _ = fs::remove_file(path);
+        } else {
+            let _ = fs::rename(path, path.with_extension("json.imported"));
+        }
+    }
+    remove_plaintext_backups(&accounts_path);
+    Ok(())
+}
+
+// Backups written by earlier releases may hold the accounts in plaintext.
+fn remove_plaintext_backups(accounts_path: &Path) {
+    for backup_path in storage::list_backups(accounts_path) {
+        let encrypted = fs::read_to_string(&backup_path)
+            .map(|content| vault::is_vault(&content))
+            .unwrap_or(false);
+        if !encrypted {
+            let _ = fs::remove_file(backup_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const ACCOUNTS_V0: &str = include_str!("../tests/fixtures/migrations/accounts_v0.json");
+    const SETTINGS_V0: &str = include_str!("../tests/fixtures/migrations/settings_v0.json");
+    const CATEGORIES_V0: &str = include_str!("../tests/fixtures/migrations/categories_v0.json");
+
+    fn account(id: &str, category: &str) -> Account {
+        Account {
+            id: id.to_string(),
+            name: format!("Account {}", id),
+            username: format!("user{}", id),
+            password: format!("password{}", id),
+            email: None,
+            category: category.to_string(),
+            last_login: None,
+            login_count: 0,
+            game_type: "league".to_string(),
+            patchline: None,
+        }
+    }
+
+    fn user_version(conn: &Connection) -> u32 {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    fn names_in(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn database(dir: &tempfile::TempDir) -> PathBuf {
+        dir.path().join(DATABASE_FILE)
+    }
+
+    fn keyfile_vault(dir: &tempfile::TempDir) -> (Connection, VaultKey) {
+        let conn = open(&database(dir)).unwrap();
+        let key = vault::load_or_create_key(&dir.path().join("vault.key")).unwrap();
+        init_vault(&conn, &key).unwrap();
+        (conn, key)
+    }
+
+    #[test]
+    fn creates_the_schema_and_snapshots_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = database(&dir);
+        let conn = open(&path).unwrap();
+
+        assert_eq!(user_version(&conn), SCHEMA.len() as u32);
+        assert!(load_settings(&conn).unwrap().is_none());
+        assert!(vault_record(&conn).unwrap().is_none());
+        assert_eq!(storage::list_backups(&path).len(), 1);
+    }
+
+    #[test]
+    fn migrates_an_older_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = database(&dir);
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(SCHEMA[0]).unwrap();
+            conn.pragma_update(None, "user_version", 1).unwrap();
+            conn.execute(
+                "INSERT INTO accounts (id, name, credentials, game_type) VALUES ('1', 'Old', x'00', 'league')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let conn = open(&path).unwrap();
+        assert_eq!(user_version(&conn), SCHEMA.len() as u32);
+        let (name, patchline): (String, Option<String>) = conn
+            .query_row(
+                "SELECT name, patchline FROM accounts WHERE id = '1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(name, "Old");
+        assert_eq!(patchline, None);
+    }
+
+    #[test]
+    fn refuses_a_newer_database_without_touching_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = database(&dir);
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.pragma_update(None, "user_version", SCHEMA.len() as u32 + 1)
+                .unwrap();
+        }
+
+        assert_eq!(
+            open(&path).unwrap_err(),
+            NidaleeError::DatabaseTooNew {
+                version: SCHEMA.len() as u32 + 1,
+                supported: SCHEMA.len() as u32,
+            }
+        );
+        assert_eq!(names_in(dir.path()), [DATABASE_FILE]);
+    }
+
+    #[test]
+    fn restores_a_corrupt_database_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = database(&dir);
+        {
+            let conn = open(&path).unwrap();
+            save_categories(&conn, &["Ranked".to_string()]).unwrap();
+        }
+        // The second open snapshots the database with the category in it.
+        drop(open(&path).unwrap());
+        fs::write(
+            &path,
+            b"not a database, just some bytes that are long enough",
+        )
+        .unwrap();
+
+        let conn = open(&path).unwrap();
+        assert_eq!(load_categories(&conn).unwrap(), ["Ranked"]);
+        assert!(names_in(dir.path())
+            .iter()
+            .any(|name| name.starts_with("nidalee.db.corrupt-")));
+        assert_eq!(storage::list_backups(&path).len(), 2);
+    }
+
+    #[test]
+    fn starts_empty_without_replacing_unusable_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = database(&dir);
+        let backup_path = storage::new_backup_path(&path).unwrap();
+        fs::write(
+            &backup_path,
+            b"also not a database, but long enough to be read",
+        )
+        .unwrap();
+        fs::write(
+            &path,
+            b"not a database, just some bytes that are long enough",
+        )
+        .unwrap();
+
+        let conn = open(&path).unwrap();
+        assert!(load_categories(&conn).unwrap().is_empty());
+        assert_eq!(storage::list_backups(&path), [backup_path]);
+    }
+
+    #[test]
+    fn saves_and_loads_encrypted_accounts() {
+        let dir = tempfile::tempdir().unwrap();
+        let (conn, key) = keyfile_vault(&dir);
+        save_account(&conn, &key, &account("1", "Ranked")).unwrap();
+
+        let sealed: Vec<u8> = conn
+            .query_row(
+                "SELECT credentials FROM accounts WHERE id = '1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&sealed).contains("password1"));
+
+        let accounts = load_accounts(&conn, &key).unwrap();
+        assert_eq!(accounts["1"].password, "password1");
+        assert_eq!(accounts["1"].category, "Ranked");
+        assert!(load_accounts(&conn, &VaultKey::generate()).is_err());
+    }
+
+    #[test]
+    fn categories_are_a_foreign_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let (conn, key) = keyfile_vault(&dir);
+        save_categories(&conn, &["Normals".to_string()]).unwrap();
+        save_account(&conn, &key, &account("1", "Ranked")).unwrap();
+        assert_eq!(load_categories(&conn).unwrap(), ["Normals", "Ranked"]);
+
+        assert!(conn
+            .execute(
+                "UPDATE accounts SET category = 'Missing' WHERE id = '1'",
+                []
+            )
+            .is_err());
+
+        conn.execute(
+            "UPDATE categories SET name = 'Solo' WHERE name = 'Ranked'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(load_accounts(&conn, &key).unwrap()["1"].category, "Solo");
+
+        save_categories(&conn, &["Normals".to_string()]).unwrap();
+        assert_eq!(load_accounts(&conn, &key).unwrap()["1"].category, "");
+    }
+
+    #[test]
+    fn records_launches() {
+        let dir = tempfile::tempdir().unwrap();
+        let (conn, key) = keyfile_vault(&dir);
+        save_account(&conn, &key, &account("1", "")).unwrap();
+
+        record_launch(&conn, "1", "league", false).unwrap();
+        let launched_at = record_launch(&conn, "1", "league", true).unwrap();
+        record_launch(&conn, "missing", "league", true).unwrap();
+
+        let launched = &load_accounts(&conn, &key).unwrap()["1"];
+        assert_eq!(launched.login_count, 1);
+        assert_eq!(launched.last_login, Some(launched_at));
+        let history: u32 = conn
+            .query_row("SELECT COUNT(*) FROM launch_history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(history, 2);
+
+        delete_account(&conn, "1").unwrap();
+        let history: u32 = conn
+            .query_row("SELECT COUNT(*) FROM launch_history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(history, 0);
+    }
+
+    #[test]
+    fn rekeys_every_account() {
+        let dir = tempfile::tempdir().unwrap();
+        let (conn, key) = keyfile_vault(&dir);
+        save_account(&conn, &key, &account("1", "")).unwrap();
+        save_account(&conn, &key, &account("2", "")).unwrap();
+
+        let new_key = vault::new_password_key("master").unwrap();
+        rekey(&conn, &key, &new_key).unwrap();
+
+        assert_eq!(load_accounts(&conn, &new_key).unwrap().len(), 2);
+        assert!(load_accounts(&conn, &key).is_err());
+        let record = vault_record(&conn).unwrap().unwrap();
+        assert_eq!(record.kdf.as_ref(), new_key.kdf());
+        assert!(verify_key(&record, &new_key));
+        assert!(!verify_key(&record, &key));
+
+        let unlocked = unlock_with_password(&conn, "master").unwrap();
+        assert_eq!(load_accounts(&conn, &unlocked).unwrap().len(), 2);
+        assert_eq!(
+            unlock_with_password(&conn, "wrong").err(),
+            Some(NidaleeError::WrongPassword)
+        );
+    }
+
+    #[test]
+    fn imports_plaintext_json_and_scrubs_its_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let accounts_path = dir.path().join("accounts.json");
+        fs::write(&accounts_path, ACCOUNTS_V0).unwrap();
+        fs::write(dir.path().join("settings.json"), SETTINGS_V0).unwrap();
+        fs::write(dir.path().join("categories.json"), CATEGORIES_V0).unwrap();
+        fs::write(
+            storage::new_backup_path(&accounts_path).unwrap(),
+            ACCOUNTS_V0,
+        )
+        .unwrap();
+
+        let conn = open(&database(&dir)).unwrap();
+        import_legacy_json(&conn, dir.path()).unwrap();
+
+        let key = vault::load_or_create_key(&dir.path().join("vault.key")).unwrap();
+        let accounts = load_accounts(&conn, &key).unwrap();
+        assert_eq!(accounts["1700000000000"].username, "fixture_main");
+        assert_eq!(accounts["1700000000000"].login_count, 0);
+        assert_eq!(load_categories(&conn).unwrap(), ["Ranked", "Normals"]);
+        assert_eq!(load_settings(&conn).unwrap().unwrap().login_delay, 8);
+
+        assert!(!accounts_path.exists());
+        assert!(storage::list_backups(&accounts_path).is_empty());
+        assert!(dir.path().join("settings.json.imported").exists());
+        assert!(dir.path().join("categories.json.imported").exists());
+
+        // A second run finds the marker and leaves a new file alone, but still
+        // removes plaintext backups left behind by an earlier import.
+        fs::write(dir.path().join("settings.json"), SETTINGS_V0).unwrap();
+        fs::write(
+            storage::new_backup_path(&accounts_path).unwrap(),
+            ACCOUNTS_V0,
+        )
+        .unwrap();
+        import_legacy_json(&conn, dir.path()).unwrap();
+        assert!(dir.path().join("settings.json").exists());
+        assert!(storage::list_backups(&accounts_path).is_empty());
+    }
+
+    #[test]
+    fn imports_a_keyfile_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = vault::load_or_create_key(&dir.path().join("vault.key")).unwrap();
+        let sealed = vault::seal_file(&key, 1, 1, ACCOUNTS_V0.as_bytes());
+        let accounts_path = dir.path().join("accounts.json");
+        fs::write(&accounts_path, &sealed).unwrap();
+        fs::write(storage::new_backup_path(&accounts_path).unwrap(), &sealed).unwrap();
+
+        let conn = open(&database(&dir)).unwrap();
+        import_legacy_json(&conn, dir.path()).unwrap();
+
+        assert_eq!(load_accounts(&conn, &key).unwrap().len(), 2);
+        assert!(verify_key(&vault_record(&conn).unwrap().unwrap(), &key));
+        assert!(dir.path().join("accounts.json.imported").exists());
+        // Encrypted backups are kept.
+        assert_eq!(storage::list_backups(&accounts_path).len(), 1);
+    }
+
+    #[test]
+    fn imports_a_password_vault_on_first_unlock() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = vault::new_password_key("master").unwrap();
+        let sealed = vault::seal_file(&key, 1, 1, ACCOUNTS_V0.as_bytes());
+        fs::write(dir.path().join("accounts.json"), &sealed).unwrap();
+
+        let conn = open(&database(&dir)).unwrap();
+        import_legacy_json(&conn, dir.path()).unwrap();
+
+        let record = vault_record(&conn).unwrap().unwrap();
+        assert_eq!(record.kdf.as_ref(), key.kdf());
+        assert_eq!(record.pending_import.as_deref(), Some(sealed.as_str()));
+        assert!(!dir.path().join("vault.key").exists());
+
+        assert_eq!(
+            unlock_with_password(&conn, "wrong").err(),
+            Some(NidaleeError::WrongPassword)
+        );
+        assert!(vault_record(&conn)
+            .unwrap()
+            .unwrap()
+            .pending_import
+            .is_some());
+
+        let unlocked = unlock_with_password(&conn, "master").unwrap();
+        assert_eq!(load_accounts(&conn, &unlocked).unwrap().len(), 2);
+        let record = vault_record(&conn).unwrap().unwrap();
+        assert!(record.pending_import.is_none());
+        assert!(verify_key(&record, &unlocked));
+    }
+
+    #[test]
+    fn keeps_the_json_files_when_the_import_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        // Sealed with a key other than the one in vault.key.
+        vault::load_or_create_key(&dir.path().join("vault.key")).unwrap();
+        let sealed = vault::seal_file(&VaultKey::generate(), 1, 1, ACCOUNTS_V0.as_bytes());
+        fs::write(dir.path().join("accounts.json"), &sealed).unwrap();
+        fs::write(dir.path().join("settings.json"), SETTINGS_V0).unwrap();
+
+        let conn = open(&database(&dir)).unwrap();
+        assert!(import_legacy_json(&conn, dir.path()).is_err());
+
+        assert!(dir.path().join("accounts.json").exists());
+        assert!(dir.path().join("settings.json").exists());
+        assert!(!dir.path().join("settings.json.imported").exists());
+        assert!(get_meta(&conn, "json_imported").unwrap().is_none());
+        assert!(load_settings(&conn).unwrap().is_none());
+    }
+}
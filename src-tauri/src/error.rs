@@ -0,0 +1,279 @@
+use crate::launch;
+use crate::platform::FOCUS_LOST;
+use crate::vault;
+use rusqlite::ErrorCode;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::{json, Value};
+use std::fmt;
+
+// Returned by every command. The frontend branches on `code`, which must stay
+// stable; `message` is English text for display and may change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NidaleeError {
+    VaultLocked,
+    VaultUnavailable(String),
+    WrongPassword,
+    PasswordRequired,
+    NoMasterPassword,
+    UnknownAccount(String),
+    ClientNotFound,
+    UnknownGame(String),
+    GameNotInstalled { game: String, patchline: String },
+    GameAlreadyRunning,
+    LaunchInProgress,
+    UpdateTimeout,
+    LaunchCancelled,
+    FocusLost,
+    LaunchFailed { attempts: u32, reason: String },
+    ScanCancelled,
+    DatabaseBusy,
+    DatabaseTooNew { version: u32, supported: u32 },
+    Io(String),
+    Parse(String),
+    InvalidInput(String),
+    Internal(String),
+}
+
+impl NidaleeError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            NidaleeError::VaultLocked => "vault_locked",
+            NidaleeError::VaultUnavailable(_) => "vault_unavailable",
+            NidaleeError::WrongPassword => "wrong_password",
+            NidaleeError::PasswordRequired => "password_required",
+            NidaleeError::NoMasterPassword => "no_master_password",
+            NidaleeError::UnknownAccount(_) => "unknown_account",
+            NidaleeError::ClientNotFound => "client_not_found",
+            NidaleeError::UnknownGame(_) => "unknown_game",
+            NidaleeError::GameNotInstalled { .. } => "game_not_installed",
+            NidaleeError::GameAlreadyRunning => "game_already_running",
+            NidaleeError::LaunchInProgress => "launch_in_progress",
+            NidaleeError::UpdateTimeout => "update_timeout",
+            NidaleeError::LaunchCancelled => "launch_cancelled",
+            NidaleeError::FocusLost => "focus_lost",
+            NidaleeError::LaunchFailed { .. } => "launch_failed",
+            NidaleeError::ScanCancelled => "scan_cancelled",
+            NidaleeError::DatabaseBusy => "database_busy",
+            NidaleeError::DatabaseTooNew { .. } => "database_too_new",
+            NidaleeError::Io(_) => "io",
+            NidaleeError::Parse(_) => "parse",
+            NidaleeError::InvalidInput(_) => "invalid_input",
+            NidaleeError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            NidaleeError::VaultLocked => "Vault is locked".to_string(),
+            NidaleeError::VaultUnavailable(e) => format!("Could not open the account vault: {}", e),
+            NidaleeError::WrongPassword => vault::WRONG_PASSWORD.to_string(),
+            NidaleeError::PasswordRequired => "Current master password is required".to_string(),
+            NidaleeError::NoMasterPassword => {
+                "Set a master password before locking the vault".to_string()
+            }
+            NidaleeError::UnknownAccount(_) => "Account not found".to_string(),
+            NidaleeError::ClientNotFound => {
+                "Could not find Riot Client. Please set the path in Settings.".to_string()
+            }
+            NidaleeError::UnknownGame(game) => format!("Unknown game: {}", game),
+            NidaleeError::GameNotInstalled { game, patchline } => {
+                format!("{} is not installed for the {} patchline", game, patchline)
+            }
+            NidaleeError::GameAlreadyRunning => "Game is already running".to_string(),
+            NidaleeError::LaunchInProgress => "Another launch is already in progress".to_string(),
+            NidaleeError::UpdateTimeout => {
+                "Game update is taking too long. Please try again later.".to_string()
+            }
+            NidaleeError::LaunchCancelled => launch::CANCELLED.to_string(),
+            NidaleeError::FocusLost => FOCUS_LOST.to_string(),
+            NidaleeError::LaunchFailed { reason, .. } => format!(
+                "Failed to launch: {}. Please try again or launch the game manually.",
+                reason
+            ),
+            NidaleeError::ScanCancelled => "Install scan cancelled".to_string(),
+            NidaleeError::DatabaseBusy => {
+                "The database is in use by another program. Close it and try again.".to_string()
+            }
+            NidaleeError::DatabaseTooNew { version, supported } => format!(
+                "The database has schema version {}, but this version of Nidalee only supports up to {}. Please update Nidalee.",
+                version, supported
+            ),
+            NidaleeError::Io(e) => format!("File error: {}", e),
+            NidaleeError::Parse(e) => format!("Could not read data: {}", e),
+            NidaleeError::InvalidInput(e) | NidaleeError::Internal(e) => e.clone(),
+        }
+    }
+
+    pub fn details(&self) -> Value {
+        match self {
+            NidaleeError::UnknownAccount(id) => json!({ "account_id": id }),
+            NidaleeError::UnknownGame(game) => json!({ "game": game }),
+            NidaleeError::GameNotInstalled { game, patchline } => {
+                json!({ "game": game, "patchline": patchline })
+            }
+            NidaleeError::LaunchFailed { attempts, reason } => {
+                json!({ "attempts": attempts, "reason": reason })
+            }
+            NidaleeError::DatabaseTooNew { version, supported } => {
+                json!({ "version": version, "supported": supported })
+            }
+            NidaleeError::VaultUnavailable(e) | NidaleeError::Io(e) | NidaleeError::Parse(e) => {
+                json!({ "cause": e })
+            }
+            _ => Value::Null,
+        }
+    }
+}
+
+impl fmt::Display for NidaleeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message())
+    }
+}
+
+impl std::error::Error for NidaleeError {}
+
+impl Serialize for NidaleeError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("NidaleeError", 3)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.message())?;
+        error.serialize_field("details", &self.details())?;
+        error.end()
+    }
+}
+
+// Lower layers report errors as strings. Anything a caller needs to tell apart
+// gets its own variant where it is raised instead of being recognised here.
+impl From<String> for NidaleeError {
+    fn from(e: String) -> Self {
+        NidaleeError::Internal(e)
+    }
+}
+
+impl From<&str> for NidaleeError {
+    fn from(e: &str) -> Self {
+        NidaleeError::from(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for NidaleeError {
+    fn from(e: std::io::Error) -> Self {
+        NidaleeError::Io(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for NidaleeError {
+    fn from(e: serde_json::Error) -> Self {
+        NidaleeError::Parse(e.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for NidaleeError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e.sqlite_error_code() {
+            Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => NidaleeError::DatabaseBusy,
+            _ => NidaleeError::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<tauri::Error> for NidaleeError {
+    fn from(e: tauri::Error) -> Self {
+        NidaleeError::Internal(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialized(error: NidaleeError) -> Value {
+        serde_json::to_value(error).unwrap()
+    }
+
+    #[test]
+    fn serializes_code_message_and_details() {
+        assert_eq!(
+            serialized(NidaleeError::LaunchFailed {
+                attempts: 3,
+                reason: "Riot Client window not found".to_string(),
+            }),
+            json!({
+                "code": "launch_failed",
+                "message": "Failed to launch: Riot Client window not found. Please try again or launch the game manually.",
+                "details": { "attempts": 3, "reason": "Riot Client window not found" },
+            })
+        );
+        assert_eq!(
+            serialized(NidaleeError::GameNotInstalled {
+                game: "valorant".to_string(),
+                patchline: "pbe".to_string(),
+            }),
+            json!({
+                "code": "game_not_installed",
+                "message": "valorant is not installed for the pbe patchline",
+                "details": { "game": "valorant", "patchline": "pbe" },
+            })
+        );
+        assert_eq!(
+            serialized(NidaleeError::Io("disk full".to_string())),
+            json!({
+                "code": "io",
+                "message": "File error: disk full",
+                "details": { "cause": "disk full" },
+            })
+        );
+    }
+
+    #[test]
+    fn variants_without_details_serialize_null() {
+        for error in [
+            NidaleeError::VaultLocked,
+            NidaleeError::WrongPassword,
+            NidaleeError::LaunchCancelled,
+            NidaleeError::FocusLost,
+            NidaleeError::ScanCancelled,
+            NidaleeError::InvalidInput("Master password cannot be empty".to_string()),
+            NidaleeError::Internal("boom".to_string()),
+        ] {
+            let value = serialized(error.clone());
+            assert_eq!(value["code"], error.code());
+            assert_eq!(value["message"], error.message());
+            assert_eq!(value["details"], Value::Null);
+        }
+    }
+
+    #[test]
+    fn lower_layer_errors_keep_their_kind() {
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(NidaleeError::from(io).code(), "io");
+
+        let json = serde_json::from_str::<Value>("{").unwrap_err();
+        assert_eq!(NidaleeError::from(json).code(), "parse");
+
+        let sql = rusqlite::Connection::open_in_memory()
+            .unwrap()
+            .execute("SELECT * FROM missing", [])
+            .unwrap_err();
+        assert_eq!(NidaleeError::from(sql).code(), "io");
+
+        let busy = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        );
+        assert_eq!(NidaleeError::from(busy), NidaleeError::DatabaseBusy);
+    }
+
+    #[test]
+    fn plain_strings_are_internal_errors() {
+        assert_eq!(
+            NidaleeError::from(launch::CANCELLED),
+            NidaleeError::Internal(launch::CANCELLED.to_string())
+        );
+        assert_eq!(
+            NidaleeError::from("Riot Client window not found"),
+            NidaleeError::Internal("Riot Client window not found".to_string())
+        );
+    }
+}
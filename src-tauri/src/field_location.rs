@@ -0,0 +1,347 @@
+use crate::platform::WindowRect;
+use serde::{Deserialize, Serialize};
+
+// Two layouts are treated as the same if their aspect ratios are this close.
+const ASPECT_TOLERANCE: f64 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldTarget {
+    // Click here, in screen coordinates, to focus the username field.
+    Click { x: i32, y: i32 },
+    // The username field is reached by pressing Tab this many times after focusing the window.
+    Keyboard { tabs: u32 },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocatorMode {
+    #[default]
+    FixedOffsets,
+    Profiles,
+    Keyboard,
+}
+
+// A click point recorded on a client window of a known size. It is scaled to the
+// actual window, so one profile covers every DPI setting of the same layout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayoutProfile {
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    pub username_x: i32,
+    pub username_y: i32,
+}
+
+impl LayoutProfile {
+    fn aspect_error(&self, rect: &WindowRect) -> f64 {
+        if self.height <= 0 || rect.height() <= 0 {
+            return f64::INFINITY;
+        }
+        let profile = self.width as f64 / self.height as f64;
+        let window = rect.width() as f64 / rect.height() as f64;
+        (profile - window).abs() / profile
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldLocation {
+    pub mode: LocatorMode,
+    pub profiles: Vec<LayoutProfile>,
+    pub keyboard_tabs: u32,
+}
+
+impl Default for FieldLocation {
+    fn default() -> Self {
+        FieldLocation {
+            mode: LocatorMode::default(),
+            profiles: builtin_profiles(),
+            keyboard_tabs: 0,
+        }
+    }
+}
+
+// The default Riot Client login screen at 100% scaling. The point is the middle
+// of the username field in the sign-in panel on the left, estimated from the
+// 1280x720 sign-in layout rather than measured on every client release. Users
+// whose client differs can add their own profile in settings.
+pub fn builtin_profiles() -> Vec<LayoutProfile> {
+    vec![LayoutProfile {
+        name: "Riot Client 16:9".to_string(),
+        width: 1280,
+        height: 720,
+        username_x: 176,
+        username_y: 242,
+    }]
+}
+
+pub trait FieldLocator {
+    fn locate(&self, rect: Option<WindowRect>) -> Result<FieldTarget, String>;
+}
+
+pub fn locator(location: &FieldLocation) -> Box<dyn FieldLocator + '_> {
+    match location.mode {
+        LocatorMode::FixedOffsets => Box::new(FixedOffsets::default()),
+        LocatorMode::Profiles => Box::new(ProfileLocator {
+            profiles: &location.profiles,
+        }),
+        LocatorMode::Keyboard => Box::new(KeyboardNavigation {
+            tabs: location.keyboard_tabs,
+        }),
+    }
+}
+
+fn require_rect(rect: Option<WindowRect>) -> Result<WindowRect, String> {
+    match rect {
+        Some(rect) if rect.width() > 0 && rect.height() > 0 => Ok(rect),
+        _ => Err("Could not read the Riot Client window size".to_string()),
+    }
+}
+
+// Clicks at a fixed fraction of the window size.
+pub struct FixedOffsets {
+    pub x_ratio: f32,
+    pub y_ratio: f32,
+}
+
+impl Default for FixedOffsets {
+    fn default() -> Self {
+        FixedOffsets {
+            x_ratio: 0.15,
+            y_ratio: 0.30,
+        }
+    }
+}
+
+impl FieldLocator for FixedOffsets {
+    fn locate(&self, rect: Option<WindowRect>) -> Result<FieldTarget, String> {
+        let rect = require_rect(rect)?;
+        Ok(FieldTarget::Click {
+            x: rect.left + (rect.width() as f32 * self.x_ratio) as i32,
+            y: rect.top + (rect.height() as f32 * self.y_ratio) as i32,
+        })
+    }
+}
+
+// Picks the profile whose aspect ratio matches the window and scales its click point.
+pub struct ProfileLocator<'a> {
+    pub profiles: &'a [LayoutProfile],
+}
+
+impl FieldLocator for ProfileLocator<'_> {
+    fn locate(&self, rect: Option<WindowRect>) -> Result<FieldTarget, String> {
+        let rect = require_rect(rect)?;
+        let profile = self
+            .profiles
+            .iter()
+            .filter(|profile| profile.width > 0 && profile.aspect_error(&rect) <= ASPECT_TOLERANCE)
+            .min_by(|a, b| a.aspect_error(&rect).total_cmp(&b.aspect_error(&rect)))
+            .ok_or_else(|| {
+                format!(
+                    "No layout profile matches a {}x{} Riot Client window",
+                    rect.width(),
+                    rect.height()
+                )
+            })?;
+
+        let scale = rect.width() as f64 / profile.width as f64;
+        Ok(FieldTarget::Click {
+            x: rect.left + (profile.username_x as f64 * scale).round() as i32,
+            y: rect.top + (profile.username_y as f64 * scale).round() as i32,
+        })
+    }
+}
+
+// Relies on the client putting focus on the username field when the login screen
+// opens, so nothing depends on window geometry.
+pub struct KeyboardNavigation {
+    pub tabs: u32,
+}
+
+impl FieldLocator for KeyboardNavigation {
+    fn locate(&self, _rect: Option<WindowRect>) -> Result<FieldTarget, String> {
+        Ok(FieldTarget::Keyboard { tabs: self.tabs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    const CLIENT_WINDOWS: &str =
+        include_str!("../tests/fixtures/field_location/client_windows.json");
+
+    #[derive(Deserialize)]
+    struct Rect {
+        left: i32,
+        top: i32,
+        right: i32,
+        bottom: i32,
+    }
+
+    #[derive(Deserialize)]
+    struct ClientWindows {
+        windows: Vec<ClientWindow>,
+    }
+
+    // A synthetic client window rect, and where the built-in profile should put the
+    // username field in it. See the note in the fixture.
+    #[derive(Deserialize)]
+    struct ClientWindow {
+        name: String,
+        rect: Rect,
+        username: Option<(i32, i32)>,
+    }
+
+    fn client_windows() -> Vec<(ClientWindow, WindowRect)> {
+        let fixture: ClientWindows = serde_json::from_str(CLIENT_WINDOWS).unwrap();
+        fixture
+            .windows
+            .into_iter()
+            .map(|window| {
+                let Rect {
+                    left,
+                    top,
+                    right,
+                    bottom,
+                } = window.rect;
+                let rect = WindowRect {
+                    left,
+                    top,
+                    right,
+                    bottom,
+                };
+                (window, rect)
+            })
+            .collect()
+    }
+
+    fn locate(location: &FieldLocation, rect: Option<WindowRect>) -> Result<FieldTarget, String> {
+        locator(location).locate(rect)
+    }
+
+    #[test]
+    fn builtin_profile_finds_the_username_field() {
+        let location = FieldLocation {
+            mode: LocatorMode::Profiles,
+            ..FieldLocation::default()
+        };
+        for (window, rect) in client_windows() {
+            let found = locate(&location, Some(rect)).ok();
+            let expected = window.username.map(|(x, y)| FieldTarget::Click { x, y });
+            assert_eq!(found, expected, "{}", window.name);
+        }
+    }
+
+    // Checks that don't rely on the fixture's points: wherever the window is and
+    // however it is scaled, the click lands at the same relative spot, inside the
+    // left-hand sign-in panel and above the middle of the window.
+    #[test]
+    fn builtin_profile_clicks_inside_the_sign_in_panel() {
+        let location = FieldLocation {
+            mode: LocatorMode::Profiles,
+            ..FieldLocation::default()
+        };
+        let mut clicked = 0;
+        for (window, rect) in client_windows() {
+            let Ok(FieldTarget::Click { x, y }) = locate(&location, Some(rect)) else {
+                continue;
+            };
+            let x_ratio = (x - rect.left) as f64 / rect.width() as f64;
+            let y_ratio = (y - rect.top) as f64 / rect.height() as f64;
+            assert!((0.05..0.30).contains(&x_ratio), "{}", window.name);
+            assert!((0.20..0.50).contains(&y_ratio), "{}", window.name);
+
+            // Within a pixel of (176, 242) scaled by width/1280, whatever the offset.
+            let scale = rect.width() as f64 / 1280.0;
+            let expected_x = 176.0 * scale;
+            let expected_y = 242.0 * scale;
+            assert!(
+                ((x - rect.left) as f64 - expected_x).abs() <= 1.0,
+                "{}",
+                window.name
+            );
+            assert!(
+                ((y - rect.top) as f64 - expected_y).abs() <= 1.0,
+                "{}",
+                window.name
+            );
+            clicked += 1;
+        }
+        assert!(clicked >= 4);
+
+        let rect = WindowRect {
+            left: -2560,
+            top: 0,
+            right: 0,
+            bottom: 1440,
+        };
+        assert_eq!(
+            locate(&location, Some(rect)).unwrap(),
+            FieldTarget::Click { x: -2208, y: 484 }
+        );
+    }
+
+    #[test]
+    fn closest_profile_wins() {
+        let profile = |name: &str, width, height, x, y| LayoutProfile {
+            name: name.to_string(),
+            width,
+            height,
+            username_x: x,
+            username_y: y,
+        };
+        let profiles = [
+            profile("16:10", 1280, 800, 100, 100),
+            profile("16:9", 1280, 720, 176, 242),
+        ];
+        let rect = WindowRect {
+            left: 0,
+            top: 0,
+            right: 1600,
+            bottom: 900,
+        };
+        assert_eq!(
+            ProfileLocator {
+                profiles: &profiles
+            }
+            .locate(Some(rect)),
+            Ok(FieldTarget::Click { x: 220, y: 303 })
+        );
+    }
+
+    #[test]
+    fn offsets_and_profiles_need_the_window_size() {
+        let (_, rect) = client_windows().remove(0);
+        assert_eq!(
+            locate(&FieldLocation::default(), Some(rect)),
+            Ok(FieldTarget::Click { x: 512, y: 396 })
+        );
+
+        let no_size = "Could not read the Riot Client window size".to_string();
+        let empty = WindowRect::default();
+        assert_eq!(
+            locate(&FieldLocation::default(), None),
+            Err(no_size.clone())
+        );
+        let profiles = FieldLocation {
+            mode: LocatorMode::Profiles,
+            ..FieldLocation::default()
+        };
+        assert_eq!(locate(&profiles, Some(empty)), Err(no_size));
+    }
+
+    #[test]
+    fn keyboard_navigation_ignores_the_window() {
+        let location = FieldLocation {
+            mode: LocatorMode::Keyboard,
+            keyboard_tabs: 2,
+            ..FieldLocation::default()
+        };
+        assert_eq!(
+            locate(&location, None),
+            Ok(FieldTarget::Keyboard { tabs: 2 })
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use crate::error::NidaleeError;
+use crate::platform::{ProcessInfo, ProcessList};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+pub const USER_GAMES_FILE: &str = "games.json";
+
+// Processes shared by every game. They are only closed when no other game needs them.
+pub const RIOT_CLIENT_PROCESSES: &[&str] = &[
+    "RiotClientServices.exe",
+    "RiotClientUx.exe",
+    "RiotClientUxRender.exe",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameDefinition {
+    pub id: String,
+    pub display_name: String,
+    pub product_id: String,
+    // The first entry is used for accounts that don't pick a patchline.
+    #[serde(default = "default_patchlines")]
+    pub patchlines: Vec<String>,
+    // Any of these running means the game is running.
+    pub processes: Vec<String>,
+    // Processes to shut down when closing the game. Falls back to `processes`.
+    #[serde(default)]
+    pub close_processes: Vec<String>,
+}
+
+fn default_patchlines() -> Vec<String> {
+    vec!["live".to_string()]
+}
+
+impl GameDefinition {
+    fn builtin(
+        id: &str,
+        display_name: &str,
+        product_id: &str,
+        patchlines: &[&str],
+        processes: &[&str],
+        close_processes: &[&str],
+    ) -> Self {
+        GameDefinition {
+            id: id.to_string(),
+            display_name: display_name.to_string(),
+            product_id: product_id.to_string(),
+            patchlines: patchlines.iter().map(|name| name.to_string()).collect(),
+            processes: processes.iter().map(|name| name.to_string()).collect(),
+            close_processes: close_processes
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+
+    pub fn patchline<'a>(&'a self, requested: Option<&'a str>) -> Result<&'a str, NidaleeError> {
+        match requested
+            .map(str::trim)
+            .filter(|patchline| !patchline.is_empty())
+        {
+            None => Ok(self.patchlines[0].as_str()),
+            Some(patchline) => self
+                .patchlines
+                .iter()
+                .find(|supported| supported.eq_ignore_ascii_case(patchline))
+                .map(String::as_str)
+                .ok_or_else(|| NidaleeError::GameNotInstalled {
+                    game: self.display_name.clone(),
+                    patchline: patchline.to_string(),
+                }),
+        }
+    }
+
+    pub fn launch_args(&self, patchline: &str) -> Vec<String> {
+        vec![
+            format!("--launch-product={}", self.product_id),
+            format!("--launch-patchline={}", patchline),
+        ]
+    }
+
+    pub fn is_game_process(&self, process: &ProcessInfo) -> bool {
+        self.processes.iter().any(|name| process.is_named(name))
+    }
+
+    pub fn close_list(&self) -> &[String] {
+        if self.close_processes.is_empty() {
+            &self.processes
+        } else {
+            &self.close_processes
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("game id cannot be empty".to_string());
+        }
+        if self.product_id.trim().is_empty() {
+            return Err(format!("{} has no product id", self.id));
+        }
+        if self.patchlines.is_empty() {
+            return Err(format!("{} lists no patchlines", self.id));
+        }
+        // Patchlines are passed to the Riot Client as --launch-patchline=<name>.
+        if let Some(patchline) = self
+            .patchlines
+            .iter()
+            .find(|patchline| patchline.is_empty() || patchline.contains(char::is_whitespace))
+        {
+            return Err(format!(
+                "{} lists an invalid patchline {:?}",
+                self.id, patchline
+            ));
+        }
+        if self.processes.is_empty() {
+            return Err(format!("{} lists no processes", self.id));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UserGamesFile {
+    games: Vec<GameDefinition>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameRegistry {
+    games: Vec<GameDefinition>,
+}
+
+impl GameRegistry {
+    pub fn builtin() -> Self {
+        GameRegistry {
+            games: vec![
+                GameDefinition::builtin(
+                    "league",
+                    "League of Legends",
+                    "league_of_legends",
+                    &["live", "pbe"],
+                    &[
+                        "League of Legends.exe",
+                        "LeagueClient.exe",
+                        "LeagueClientUx.exe",
+                    ],
+                    &[
+                        "League of Legends.exe",
+                        "LeagueClient.exe",
+                        "LeagueClientUx.exe",
+                        "LeagueClientUxRender.exe",
+                    ],
+                ),
+                GameDefinition::builtin(
+                    "valorant",
+                    "VALORANT",
+                    "valorant",
+                    &["live", "pbe"],
+                    &["VALORANT.exe", "VALORANT-Win64-Shipping.exe"],
+                    &[],
+                ),
+                GameDefinition::builtin(
+                    "lor",
+                    "Legends of Runeterra",
+                    "bacon",
+                    &["live"],
+                    &["LoR.exe"],
+                    &[],
+                ),
+            ],
+        }
+    }
+
+    // Built-in games plus the user's games file. User entries with a built-in id replace it.
+    pub fn load(path: &Path) -> Self {
+        let mut registry = GameRegistry::builtin();
+        let Ok(content) = fs::read_to_string(path) else {
+            return registry;
+        };
+
+        match serde_json::from_str::<UserGamesFile>(&content) {
+            Ok(file) => registry.merge(file.games),
+            Err(e) => warn!("Ignoring {}: {}", path.display(), e),
+        }
+        registry
+    }
+
+    pub fn merge(&mut self, games: Vec<GameDefinition>) {
+        let mut merged: Vec<String> = Vec::new();
+        for game in games {
+            if let Err(e) = game.validate() {
+                warn!("Skipping game definition: {}", e);
+                continue;
+            }
+            if merged.contains(&game.id) {
+                warn!("Skipping game definition: {} is listed twice", game.id);
+                continue;
+            }
+            merged.push(game.id.clone());
+            match self
+                .games
+                .iter_mut()
+                .find(|existing| existing.id == game.id)
+            {
+                Some(existing) => *existing = game,
+                None => self.games.push(game),
+            }
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&GameDefinition> {
+        self.games.iter().find(|game| game.id == id)
+    }
+
+    pub fn all(&self) -> &[GameDefinition] {
+        &self.games
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct GameState {
+    pub running: bool,
+    pub processes: Vec<ProcessInfo>,
+}
+
+pub type GameStatus = BTreeMap<String, GameState>;
+
+pub fn game_status(
+    games: &GameRegistry,
+    processes: &dyn ProcessList,
+) -> Result<GameStatus, String> {
+    let running = processes.list()?;
+
+    Ok(games
+        .all()
+        .iter()
+        .map(|game| {
+            let processes: Vec<ProcessInfo> = running
+                .iter()
+                .filter(|process| game.is_game_process(process))
+                .cloned()
+                .collect();
+            let state = GameState {
+                running: !processes.is_empty(),
+                processes,
+            };
+            (game.id.clone(), state)
+        })
+        .collect())
+}
+
+pub fn is_game_running(status: &GameStatus, game_id: &str) -> bool {
+    status
+        .get(game_id)
+        .map(|game| game.running)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::fake::{process, FakePlatform, FakeState};
+
+    #[test]
+    fn reports_which_games_are_running() {
+        let fake = FakePlatform::new(FakeState {
+            processes: vec![
+                process(10, "RiotClientServices.exe", None),
+                process(20, "leagueclient.exe", Some(10)),
+                process(21, "LeagueClientUxRender.exe", Some(20)),
+                process(30, "explorer.exe", None),
+            ],
+            ..FakeState::default()
+        });
+        let status = game_status(&GameRegistry::builtin(), &fake).unwrap();
+
+        assert_eq!(
+            status.keys().collect::<Vec<_>>(),
+            ["league", "lor", "valorant"]
+        );
+        assert!(is_game_running(&status, "league"));
+        assert_eq!(
+            status["league"].processes,
+            vec![process(20, "leagueclient.exe", Some(10))]
+        );
+        assert!(!is_game_running(&status, "valorant"));
+        assert!(status["valorant"].processes.is_empty());
+        assert!(!is_game_running(&status, "unknown"));
+    }
+
+    fn user_games(json: &str) -> GameRegistry {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(USER_GAMES_FILE);
+        fs::write(&path, json).unwrap();
+        GameRegistry::load(&path)
+    }
+
+    fn ids(registry: &GameRegistry) -> Vec<&str> {
+        registry.all().iter().map(|game| game.id.as_str()).collect()
+    }
+
+    #[test]
+    fn loads_the_builtin_games_without_a_user_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = GameRegistry::load(&dir.path().join(USER_GAMES_FILE));
+        assert_eq!(ids(&registry), ["league", "valorant", "lor"]);
+        assert_eq!(user_games("not json").all(), GameRegistry::builtin().all());
+    }
+
+    #[test]
+    fn user_games_override_and_extend_the_builtins() {
+        let registry = user_games(
+            r#"{ "games": [
+                { "id": "valorant", "display_name": "VALORANT (PBE)", "product_id": "valorant",
+                  "patchlines": ["pbe"], "processes": ["VALORANT-Win64-Shipping.exe"] },
+                { "id": "2xko", "display_name": "2XKO", "product_id": "lion",
+                  "processes": ["2XKO.exe"] }
+            ] }"#,
+        );
+
+        assert_eq!(ids(&registry), ["league", "valorant", "lor", "2xko"]);
+        let valorant = registry.get("valorant").unwrap();
+        assert_eq!(valorant.display_name, "VALORANT (PBE)");
+        assert_eq!(valorant.patchlines, ["pbe"]);
+        assert_eq!(valorant.close_list(), ["VALORANT-Win64-Shipping.exe"]);
+
+        let added = registry.get("2xko").unwrap();
+        assert_eq!(added.patchlines, ["live"]);
+        assert_eq!(added.product_id, "lion");
+    }
+
+    #[test]
+    fn skips_invalid_user_games() {
+        let registry = user_games(
+            r#"{ "games": [
+                { "id": "league", "display_name": "No processes", "product_id": "league_of_legends",
+                  "processes": [] },
+                { "id": "lor", "display_name": "Bad patchline", "product_id": "bacon",
+                  "patchlines": ["live", "public beta"], "processes": ["LoR.exe"] },
+                { "id": "", "display_name": "No id", "product_id": "x", "processes": ["x.exe"] },
+                { "id": "2xko", "display_name": "First", "product_id": "lion",
+                  "processes": ["2XKO.exe"] },
+                { "id": "2xko", "display_name": "Second", "product_id": "lion",
+                  "processes": ["2XKO.exe"] }
+            ] }"#,
+        );
+
+        let builtin = GameRegistry::builtin();
+        assert_eq!(registry.get("league"), builtin.get("league"));
+        assert_eq!(registry.get("lor"), builtin.get("lor"));
+        assert_eq!(ids(&registry), ["league", "valorant", "lor", "2xko"]);
+        assert_eq!(registry.get("2xko").unwrap().display_name, "First");
+    }
+
+    #[test]
+    fn picks_the_account_patchline() {
+        let registry = GameRegistry::builtin();
+        let league = registry.get("league").unwrap();
+        assert_eq!(league.patchline(None).unwrap(), "live");
+        assert_eq!(league.patchline(Some("  ")).unwrap(), "live");
+        assert_eq!(league.patchline(Some(" PBE ")).unwrap(), "pbe");
+    }
+
+    #[test]
+    fn launches_the_product_on_its_patchline() {
+        let registry = GameRegistry::builtin();
+        assert_eq!(
+            registry.get("league").unwrap().launch_args("pbe"),
+            [
+                "--launch-product=league_of_legends",
+                "--launch-patchline=pbe"
+            ]
+        );
+        assert_eq!(
+            registry.get("lor").unwrap().launch_args("live"),
+            ["--launch-product=bacon", "--launch-patchline=live"]
+        );
+    }
+
+    #[test]
+    fn rejects_an_unknown_patchline() {
+        let registry = GameRegistry::builtin();
+        assert_eq!(
+            registry.get("lor").unwrap().patchline(Some("pbe")),
+            Err(NidaleeError::GameNotInstalled {
+                game: "Legends of Runeterra".to_string(),
+                patchline: "pbe".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn nothing_running() {
+        let status = game_status(&GameRegistry::builtin(), &FakePlatform::default()).unwrap();
+        assert!(status.values().all(|game| !game.running));
+    }
+}
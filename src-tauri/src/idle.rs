@@ -0,0 +1,120 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+pub struct IdleTimer<C: Clock = SystemClock> {
+    clock: C,
+    last_activity: Mutex<Instant>,
+}
+
+impl<C: Clock> IdleTimer<C> {
+    pub fn new(clock: C) -> Self {
+        let now = clock.now();
+        IdleTimer {
+            clock,
+            last_activity: Mutex::new(now),
+        }
+    }
+
+    pub fn touch(&self) {
+        *self.last_activity.lock().unwrap() = self.clock.now();
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        let last_activity = *self.last_activity.lock().unwrap();
+        self.clock.now().saturating_duration_since(last_activity)
+    }
+
+    pub fn is_expired(&self, timeout: Duration) -> bool {
+        !timeout.is_zero() && self.idle_for() >= timeout
+    }
+}
+
+// Time only moves when a test advances it or something sleeps on it.
+#[cfg(test)]
+#[derive(Clone)]
+pub struct FakeClock {
+    now: std::sync::Arc<Mutex<Instant>>,
+}
+
+#[cfg(test)]
+impl FakeClock {
+    pub fn new() -> Self {
+        FakeClock {
+            now: std::sync::Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+    #[test]
+    fn expires_once_the_timeout_has_passed() {
+        let clock = FakeClock::new();
+        let timer = IdleTimer::new(clock.clone());
+
+        clock.advance(TIMEOUT - Duration::from_secs(1));
+        assert!(!timer.is_expired(TIMEOUT));
+        clock.advance(Duration::from_secs(1));
+        assert!(timer.is_expired(TIMEOUT));
+        assert_eq!(timer.idle_for(), TIMEOUT);
+    }
+
+    #[test]
+    fn touch_restarts_the_timeout() {
+        let clock = FakeClock::new();
+        let timer = IdleTimer::new(clock.clone());
+
+        clock.advance(TIMEOUT - Duration::from_secs(1));
+        timer.touch();
+        clock.advance(TIMEOUT - Duration::from_secs(1));
+        assert!(!timer.is_expired(TIMEOUT));
+        clock.advance(Duration::from_secs(1));
+        assert!(timer.is_expired(TIMEOUT));
+    }
+
+    #[test]
+    fn zero_timeout_never_expires() {
+        let clock = FakeClock::new();
+        let timer = IdleTimer::new(clock.clone());
+
+        assert!(!timer.is_expired(Duration::ZERO));
+        clock.advance(Duration::from_secs(24 * 60 * 60));
+        assert!(!timer.is_expired(Duration::ZERO));
+    }
+}
@@ -0,0 +1,347 @@
+use crate::games::GameRegistry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR_STR};
+use tracing::warn;
+
+pub const CLIENT_INSTALLS_FILE: &str = "RiotClientInstalls.json";
+
+pub fn riot_games_dir() -> Option<PathBuf> {
+    let program_data = env::var_os("ProgramData")?;
+    let dir = PathBuf::from(program_data).join("Riot Games");
+    dir.is_dir().then_some(dir)
+}
+
+// The Riot Client keeps one folder per installed product and patchline, e.g.
+// ProgramData\Riot Games\Metadata\valorant.pbe\valorant.pbe.product_settings.yaml
+pub fn metadata_dir() -> Option<PathBuf> {
+    let dir = riot_games_dir()?.join("Metadata");
+    dir.is_dir().then_some(dir)
+}
+
+pub fn product_settings_path(metadata_dir: &Path, product_id: &str, patchline: &str) -> PathBuf {
+    let name = format!("{}.{}", product_id, patchline);
+    metadata_dir
+        .join(&name)
+        .join(format!("{}.product_settings.yaml", name))
+}
+
+pub fn is_patchline_installed(metadata_dir: &Path, product_id: &str, patchline: &str) -> bool {
+    product_settings_path(metadata_dir, product_id, patchline).is_file()
+}
+
+// Only the keys we use; the file carries many more.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ProductSettings {
+    pub product_install_full_path: Option<String>,
+    pub product_install_root: Option<String>,
+    pub should_repair: bool,
+    pub version: Option<String>,
+}
+
+pub fn parse_product_settings(content: &str) -> Result<ProductSettings, String> {
+    serde_yaml::from_str(content).map_err(|e| e.to_string())
+}
+
+pub fn read_product_settings(
+    metadata_dir: &Path,
+    product_id: &str,
+    patchline: &str,
+) -> Result<ProductSettings, String> {
+    let path = product_settings_path(metadata_dir, product_id, patchline);
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    parse_product_settings(&content)
+}
+
+// ProgramData\Riot Games\RiotClientInstalls.json, written by the client installer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ClientInstalls {
+    pub rc_default: Option<String>,
+    pub rc_live: Option<String>,
+    pub rc_beta: Option<String>,
+    // Game install folder to the client that manages it.
+    pub associated_client: BTreeMap<String, String>,
+}
+
+impl ClientInstalls {
+    // Paths that still point at an existing client, preferred one first.
+    pub fn client_paths(&self) -> Vec<PathBuf> {
+        [&self.rc_default, &self.rc_live, &self.rc_beta]
+            .into_iter()
+            .flatten()
+            .chain(self.associated_client.values())
+            .map(|path| normalize(path))
+            .filter(|path| path.is_file())
+            .collect()
+    }
+}
+
+pub fn parse_client_installs(content: &str) -> Result<ClientInstalls, String> {
+    serde_json::from_str(content).map_err(|e| e.to_string())
+}
+
+pub fn read_client_installs(riot_games_dir: &Path) -> Result<ClientInstalls, String> {
+    let content =
+        fs::read_to_string(riot_games_dir.join(CLIENT_INSTALLS_FILE)).map_err(|e| e.to_string())?;
+    parse_client_installs(&content)
+}
+
+// The client writes forward slashes, sometimes with a trailing one.
+fn normalize(path: &str) -> PathBuf {
+    PathBuf::from(
+        path.trim()
+            .trim_end_matches(['/', '\\'])
+            .replace('/', MAIN_SEPARATOR_STR),
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameInstall {
+    pub game_id: String,
+    pub patchline: String,
+    pub path: String,
+    pub version: Option<String>,
+}
+
+pub fn validate_install(path: &Path, executables: &[String]) -> Result<(), String> {
+    if !path.is_absolute() {
+        return Err(format!("{} is not an absolute path", path.display()));
+    }
+    if !path.is_dir() {
+        return Err(format!("{} does not exist", path.display()));
+    }
+    // Some games keep their executable one folder down, e.g. LoR\live\Game\LoR.exe.
+    let subdirs: Vec<PathBuf> = fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|entry| entry.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    let found = executables
+        .iter()
+        .any(|exe| path.join(exe).is_file() || subdirs.iter().any(|dir| dir.join(exe).is_file()));
+    if !found {
+        return Err(format!("{} does not contain the game", path.display()));
+    }
+    Ok(())
+}
+
+// Every installed patchline of every known game, according to the client's metadata.
+// Installs whose folder is gone or doesn't hold the game are skipped.
+pub fn discover(metadata_dir: &Path, games: &GameRegistry) -> Vec<GameInstall> {
+    let mut installs = Vec::new();
+    for game in games.all() {
+        for patchline in &game.patchlines {
+            if !is_patchline_installed(metadata_dir, &game.product_id, patchline) {
+                continue;
+            }
+            let settings = match read_product_settings(metadata_dir, &game.product_id, patchline) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    warn!("Failed to read {} {} settings: {}", game.id, patchline, e);
+                    continue;
+                }
+            };
+            let Some(path) = settings.product_install_full_path.as_deref().map(normalize) else {
+                continue;
+            };
+            if let Err(e) = validate_install(&path, &game.processes) {
+                warn!("Ignoring {} {} install: {}", game.id, patchline, e);
+                continue;
+            }
+            installs.push(GameInstall {
+                game_id: game.id.clone(),
+                patchline: patchline.clone(),
+                path: path.to_string_lossy().into_owned(),
+                version: settings.version,
+            });
+        }
+    }
+    installs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const CLIENT_INSTALLS: &str =
+        include_str!("../tests/fixtures/installs/RiotClientInstalls.json");
+    const LEAGUE_SETTINGS: &str =
+        include_str!("../tests/fixtures/installs/league_of_legends.live.product_settings.yaml");
+    const VALORANT_SETTINGS: &str =
+        include_str!("../tests/fixtures/installs/valorant.live.product_settings.yaml");
+    const LOR_SETTINGS: &str =
+        include_str!("../tests/fixtures/installs/bacon.live.product_settings.yaml");
+
+    // Points a fixture's C:/Riot Games paths into `root`, written the way the client writes them.
+    fn moved_to(root: &Path, fixture: &str) -> String {
+        let root = root.to_string_lossy().replace('\\', "/");
+        fixture.replace("C:/Riot Games", &root)
+    }
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"MZ").unwrap();
+    }
+
+    fn write_settings(metadata_dir: &Path, product_id: &str, patchline: &str, content: &str) {
+        let path = product_settings_path(metadata_dir, product_id, patchline);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn parses_the_client_installs() {
+        let installs = parse_client_installs(CLIENT_INSTALLS).unwrap();
+        let client = "C:/Riot Games/Riot Client/RiotClientServices.exe";
+        assert_eq!(installs.rc_default.as_deref(), Some(client));
+        assert_eq!(installs.rc_live.as_deref(), Some(client));
+        assert_eq!(installs.rc_beta, None);
+        assert_eq!(
+            installs.associated_client.keys().collect::<Vec<_>>(),
+            [
+                "C:/Riot Games/League of Legends/",
+                "C:/Riot Games/VALORANT/live/"
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_the_client_that_still_exists() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(
+            root.path().join(CLIENT_INSTALLS_FILE),
+            moved_to(root.path(), CLIENT_INSTALLS),
+        )
+        .unwrap();
+        let installs = read_client_installs(root.path()).unwrap();
+        assert!(installs.client_paths().is_empty());
+
+        let client = root
+            .path()
+            .join("Riot Client")
+            .join("RiotClientServices.exe");
+        touch(&client);
+        let paths = installs.client_paths();
+        assert_eq!(paths.first(), Some(&client));
+        assert!(paths.iter().all(|path| *path == client));
+    }
+
+    #[test]
+    fn parses_product_settings() {
+        assert_eq!(
+            parse_product_settings(LEAGUE_SETTINGS),
+            Ok(ProductSettings {
+                product_install_full_path: Some("C:/Riot Games/League of Legends".to_string()),
+                product_install_root: Some("C:/Riot Games/".to_string()),
+                should_repair: false,
+                version: Some("14.9.580.2298".to_string()),
+            })
+        );
+        assert_eq!(parse_product_settings(LOR_SETTINGS).unwrap().version, None);
+        assert!(parse_product_settings("should_repair: [").is_err());
+    }
+
+    #[test]
+    fn discovers_installs_that_hold_their_game() {
+        let root = tempfile::tempdir().unwrap();
+        let metadata_dir = root.path().join("Metadata");
+        write_settings(
+            &metadata_dir,
+            "league_of_legends",
+            "live",
+            &moved_to(root.path(), LEAGUE_SETTINGS),
+        );
+        write_settings(
+            &metadata_dir,
+            "valorant",
+            "live",
+            &moved_to(root.path(), VALORANT_SETTINGS),
+        );
+        write_settings(
+            &metadata_dir,
+            "bacon",
+            "live",
+            &moved_to(root.path(), LOR_SETTINGS),
+        );
+
+        let league = root.path().join("League of Legends");
+        touch(&league.join("LeagueClient.exe"));
+        // The VALORANT folder is left behind after an uninstall.
+        fs::create_dir_all(root.path().join("VALORANT").join("live")).unwrap();
+        let lor = root.path().join("LoR").join("live");
+        touch(&lor.join("Game").join("LoR.exe"));
+
+        let found = discover(&metadata_dir, &GameRegistry::builtin());
+        assert_eq!(
+            found,
+            [
+                GameInstall {
+                    game_id: "league".to_string(),
+                    patchline: "live".to_string(),
+                    path: league.to_string_lossy().into_owned(),
+                    version: Some("14.9.580.2298".to_string()),
+                },
+                GameInstall {
+                    game_id: "lor".to_string(),
+                    patchline: "live".to_string(),
+                    path: lor.to_string_lossy().into_owned(),
+                    version: None,
+                },
+            ]
+        );
+        assert!(is_patchline_installed(&metadata_dir, "valorant", "live"));
+        assert!(!is_patchline_installed(&metadata_dir, "valorant", "pbe"));
+    }
+
+    #[test]
+    fn skips_unreadable_settings() {
+        let root = tempfile::tempdir().unwrap();
+        let metadata_dir = root.path().join("Metadata");
+        write_settings(
+            &metadata_dir,
+            "league_of_legends",
+            "live",
+            "product_install_full_path: [",
+        );
+        touch(
+            &root
+                .path()
+                .join("League of Legends")
+                .join("LeagueClient.exe"),
+        );
+
+        assert!(discover(&metadata_dir, &GameRegistry::builtin()).is_empty());
+    }
+
+    #[test]
+    fn validates_the_install_folder() {
+        let root = tempfile::tempdir().unwrap();
+        let executables = ["VALORANT.exe".to_string()];
+        let install = root.path().join("VALORANT").join("live");
+
+        assert_eq!(
+            validate_install(Path::new("VALORANT/live"), &executables),
+            Err("VALORANT/live is not an absolute path".to_string())
+        );
+        assert_eq!(
+            validate_install(&install, &executables),
+            Err(format!("{} does not exist", install.display()))
+        );
+        fs::create_dir_all(&install).unwrap();
+        assert_eq!(
+            validate_install(&install, &executables),
+            Err(format!("{} does not contain the game", install.display()))
+        );
+        touch(&install.join("VALORANT.exe"));
+        assert_eq!(validate_install(&install, &executables), Ok(()));
+    }
+}
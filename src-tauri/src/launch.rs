@@ -0,0 +1,1098 @@
+use crate::credential_entry::{self, EntryMode};
+use crate::error::NidaleeError;
+use crate::field_location::{self, FieldLocation, FieldTarget};
+use crate::games::{GameDefinition, RIOT_CLIENT_PROCESSES};
+use crate::idle::Clock;
+use crate::installs;
+use crate::patch_status;
+use crate::platform::{FocusGuard, InputKey, Platform, WindowHandle, FOCUS_LOST};
+use crate::riot_api::RiotClientApi;
+use crate::shutdown::{self, CloseOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+pub const CANCELLED: &str = "Launch cancelled";
+const SIGN_OUT_LOOP: &str = "The Riot Client keeps signing back in to another account";
+// Sign-outs allowed per launch before the client is assumed to restore the old session.
+const MAX_SIGN_OUTS: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchState {
+    SpawningClient,
+    WaitingForWindow,
+    WaitingForUpdate,
+    SigningOut,
+    LoggingIn,
+    LaunchingGame,
+    Verifying,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatePolicy {
+    pub timeout_ms: u64,
+    // How many times the state may fail before the whole launch fails.
+    pub retries: u32,
+}
+
+impl StatePolicy {
+    const fn new(timeout_secs: u64, retries: u32) -> Self {
+        StatePolicy {
+            timeout_ms: timeout_secs * 1000,
+            retries,
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LaunchPolicy {
+    pub spawning_client: StatePolicy,
+    pub waiting_for_window: StatePolicy,
+    pub waiting_for_update: StatePolicy,
+    pub signing_out: StatePolicy,
+    pub logging_in: StatePolicy,
+    pub launching_game: StatePolicy,
+    pub verifying: StatePolicy,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for LaunchPolicy {
+    fn default() -> Self {
+        LaunchPolicy {
+            spawning_client: StatePolicy::new(10, 2),
+            waiting_for_window: StatePolicy::new(30, 1),
+            waiting_for_update: StatePolicy::new(180, 0),
+            signing_out: StatePolicy::new(10, 1),
+            logging_in: StatePolicy::new(10, 1),
+            launching_game: StatePolicy::new(8, 4),
+            verifying: StatePolicy::new(3, 2),
+            poll_interval_ms: 500,
+        }
+    }
+}
+
+impl LaunchPolicy {
+    pub fn for_state(&self, state: LaunchState) -> StatePolicy {
+        match state {
+            LaunchState::SpawningClient => self.spawning_client,
+            LaunchState::WaitingForWindow => self.waiting_for_window,
+            LaunchState::WaitingForUpdate => self.waiting_for_update,
+            LaunchState::SigningOut => self.signing_out,
+            LaunchState::LoggingIn => self.logging_in,
+            LaunchState::LaunchingGame => self.launching_game,
+            LaunchState::Verifying => self.verifying,
+            LaunchState::Done | LaunchState::Failed | LaunchState::Cancelled => {
+                StatePolicy::default()
+            }
+        }
+    }
+
+    fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms.max(10))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LaunchProgress {
+    pub game: String,
+    pub state: LaunchState,
+    // 1 on the first entry into a state, counting up on every retry.
+    pub attempt: u32,
+    pub message: Option<String>,
+    // Patch progress while waiting for an update.
+    pub percent: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+// Why a step failed. Only the kinds that change how the launch ends are told
+// apart; launch_error turns them into the error the frontend sees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StepError {
+    Cancelled,
+    FocusLost,
+    SignOutLoop,
+    Other(String),
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepError::Cancelled => f.write_str(CANCELLED),
+            StepError::FocusLost => f.write_str(FOCUS_LOST),
+            StepError::SignOutLoop => f.write_str(SIGN_OUT_LOOP),
+            StepError::Other(e) => f.write_str(e),
+        }
+    }
+}
+
+impl From<String> for StepError {
+    fn from(e: String) -> Self {
+        StepError::Other(e)
+    }
+}
+
+impl From<&str> for StepError {
+    fn from(e: &str) -> Self {
+        StepError::Other(e.to_string())
+    }
+}
+
+fn launch_error(error: StepError, state: LaunchState, attempts: u32) -> NidaleeError {
+    match error {
+        StepError::Cancelled => NidaleeError::LaunchCancelled,
+        StepError::FocusLost => NidaleeError::FocusLost,
+        _ if state == LaunchState::WaitingForUpdate => NidaleeError::UpdateTimeout,
+        error => NidaleeError::LaunchFailed {
+            attempts,
+            reason: error.to_string(),
+        },
+    }
+}
+
+pub struct LaunchRequest<'a> {
+    pub game: &'a GameDefinition,
+    pub patchline: &'a str,
+    pub riot_client_path: &'a str,
+    pub username: &'a str,
+    pub password: &'a str,
+    pub login_delay: Duration,
+    pub entry_mode: EntryMode,
+    pub field_location: &'a FieldLocation,
+}
+
+// Where a failed state starts over. A window that never shows up usually means the
+// client died, and a game that exits during verification has to be launched again.
+pub fn retry_from(state: LaunchState) -> LaunchState {
+    match state {
+        LaunchState::WaitingForWindow => LaunchState::SpawningClient,
+        LaunchState::Verifying => LaunchState::LaunchingGame,
+        other => other,
+    }
+}
+
+pub fn find_riot_client_window(platform: &Platform) -> Option<WindowHandle> {
+    platform
+        .windows
+        .find(Some("Chrome_WidgetWin_1"), Some("Riot Client"))
+        .or_else(|| platform.windows.find(Some("RCLIENT"), Some("Riot Client")))
+}
+
+pub struct Launcher<'a> {
+    platform: &'a Platform,
+    api: &'a dyn RiotClientApi,
+    clock: &'a dyn Clock,
+    policy: &'a LaunchPolicy,
+    cancel: CancelToken,
+}
+
+impl<'a> Launcher<'a> {
+    pub fn new(
+        platform: &'a Platform,
+        api: &'a dyn RiotClientApi,
+        clock: &'a dyn Clock,
+        policy: &'a LaunchPolicy,
+        cancel: CancelToken,
+    ) -> Self {
+        Launcher {
+            platform,
+            api,
+            clock,
+            policy,
+            cancel,
+        }
+    }
+
+    pub fn run(
+        &self,
+        request: &LaunchRequest,
+        emit: &mut dyn FnMut(LaunchProgress),
+    ) -> Result<(), NidaleeError> {
+        let mut entries: HashMap<LaunchState, u32> = HashMap::new();
+        let mut failures: HashMap<LaunchState, u32> = HashMap::new();
+        let mut state = LaunchState::SpawningClient;
+        let mut message = None;
+
+        loop {
+            let attempt = entries.entry(state).or_default();
+            *attempt += 1;
+            emit(progress(request, state, *attempt, message.take()));
+            if state == LaunchState::Done {
+                return Ok(());
+            }
+
+            let attempt = *attempt;
+            let mut report = |percent| {
+                let mut update = progress(request, state, attempt, None);
+                update.percent = Some(percent);
+                emit(update);
+            };
+            let result = self.step(state, request, &mut report).and_then(|next| {
+                let sign_outs = entries.get(&LaunchState::SigningOut).copied();
+                if next == LaunchState::SigningOut && sign_outs.unwrap_or(0) >= MAX_SIGN_OUTS {
+                    return Err(StepError::SignOutLoop);
+                }
+                Ok(next)
+            });
+            match result {
+                Ok(next) => state = next,
+                Err(e) if matches!(e, StepError::Cancelled) || self.cancel.is_cancelled() => {
+                    info!("Launch cancelled while {:?}", state);
+                    emit(progress(request, LaunchState::Cancelled, 1, None));
+                    return Err(launch_error(StepError::Cancelled, state, 1));
+                }
+                Err(e) => {
+                    let failed = failures.entry(state).or_default();
+                    *failed += 1;
+                    // Typing again after losing focus could put credentials in the wrong
+                    // place, and signing out again would only restart the loop.
+                    if *failed > self.policy.for_state(state).retries
+                        || matches!(e, StepError::FocusLost | StepError::SignOutLoop)
+                    {
+                        warn!("Launch failed while {:?}: {}", state, e);
+                        emit(progress(
+                            request,
+                            LaunchState::Failed,
+                            1,
+                            Some(e.to_string()),
+                        ));
+                        return Err(launch_error(e, state, *failed));
+                    }
+
+                    warn!("{:?} failed ({}), retrying", state, e);
+                    state = retry_from(state);
+                    if state == LaunchState::LaunchingGame {
+                        self.close_game(request.game);
+                    }
+                    message = Some(e.to_string());
+                }
+            }
+        }
+    }
+
+    fn step(
+        &self,
+        state: LaunchState,
+        request: &LaunchRequest,
+        report: &mut dyn FnMut(f32),
+    ) -> Result<LaunchState, StepError> {
+        let timeout = self.policy.for_state(state).timeout();
+        match state {
+            LaunchState::SpawningClient => {
+                info!("Launching Riot Client from: {}", request.riot_client_path);
+                self.check_cancelled()?;
+                self.platform
+                    .control
+                    .spawn_hidden(request.riot_client_path, &[])?;
+                if !self.wait_until(timeout, || self.client_running())? {
+                    return Err(format!(
+                        "Riot Client did not start within {} seconds",
+                        timeout.as_secs()
+                    )
+                    .into());
+                }
+                Ok(LaunchState::WaitingForWindow)
+            }
+            LaunchState::WaitingForWindow => {
+                if !self.wait_until(timeout, || {
+                    Ok(find_riot_client_window(self.platform).is_some())
+                })? {
+                    return Err(format!(
+                        "Riot Client window not found after {} seconds",
+                        timeout.as_secs()
+                    )
+                    .into());
+                }
+                Ok(LaunchState::WaitingForUpdate)
+            }
+            LaunchState::WaitingForUpdate => {
+                // Patching can pause between phases, so require two clean looks.
+                let metadata_dir = installs::metadata_dir();
+                let mut last_percent = None;
+                let mut updating = || {
+                    let status = patch_status::detect(
+                        self.api,
+                        metadata_dir.as_deref(),
+                        &request.game.product_id,
+                        request.patchline,
+                    );
+                    if let Some(percent) = status.percent.filter(|_| status.is_updating()) {
+                        if last_percent != Some(percent) {
+                            last_percent = Some(percent);
+                            report(percent);
+                        }
+                    }
+                    status.is_updating()
+                };
+                let settled = self.wait_until(timeout, || {
+                    if updating() {
+                        return Ok(false);
+                    }
+                    self.pause(self.policy.poll_interval())?;
+                    Ok(!updating())
+                })?;
+                if !settled {
+                    return Err("Game update is taking too long. Please try again later.".into());
+                }
+                Ok(LaunchState::LoggingIn)
+            }
+            LaunchState::LoggingIn => {
+                debug!("Waiting {:?} for client to load...", request.login_delay);
+                self.pause(request.login_delay)?;
+
+                // Typing over another signed-in account would send the credentials
+                // into its session, so only a positive answer from the API stops the
+                // login. If the API never answers, type as before the API existed.
+                let mut session = Err(String::new());
+                self.wait_until(timeout, || {
+                    session = self.api.session();
+                    Ok(session.is_ok())
+                })?;
+                match session {
+                    Ok(Some(account)) if account.is_login(request.username) => {
+                        info!("Already signed in to the right account, skipping login");
+                        return Ok(LaunchState::LaunchingGame);
+                    }
+                    Ok(Some(_)) => return Ok(LaunchState::SigningOut),
+                    Ok(None) => {}
+                    Err(e) => warn!(
+                        "Could not tell which account is signed in ({}), logging in anyway",
+                        e
+                    ),
+                }
+
+                let mut window = None;
+                self.wait_until(timeout, || {
+                    window = find_riot_client_window(self.platform);
+                    Ok(window.is_some())
+                })?;
+                let window = window.ok_or("Riot Client window closed before login")?;
+                self.log_in(window, request)?;
+                Ok(LaunchState::LaunchingGame)
+            }
+            LaunchState::SigningOut => {
+                info!("Another account is signed in, signing out first");
+                self.check_cancelled()?;
+                self.api.sign_out()?;
+                if !self.wait_until(timeout, || Ok(self.api.session()?.is_none()))? {
+                    return Err(format!(
+                        "Could not sign out of the Riot Client within {} seconds",
+                        timeout.as_secs()
+                    )
+                    .into());
+                }
+                Ok(LaunchState::LoggingIn)
+            }
+            LaunchState::LaunchingGame => {
+                info!(
+                    "Launching game: {} ({})",
+                    request.game.display_name, request.patchline
+                );
+                let ready = self.wait_for_api(timeout, || {
+                    self.api
+                        .is_product_ready(&request.game.product_id, request.patchline)
+                })?;
+                if ready == Some(false) {
+                    return Err(format!(
+                        "Riot Client was not ready to launch {} within {} seconds",
+                        request.game.display_name,
+                        timeout.as_secs()
+                    )
+                    .into());
+                }
+                self.check_cancelled()?;
+                let launch_args = request.game.launch_args(request.patchline);
+                let args: Vec<&str> = launch_args.iter().map(String::as_str).collect();
+                self.platform
+                    .control
+                    .spawn_hidden(request.riot_client_path, &args)?;
+                if !self.wait_until(timeout, || self.game_running(request.game))? {
+                    return Err(format!(
+                        "{} did not start within {} seconds",
+                        request.game.display_name,
+                        timeout.as_secs()
+                    )
+                    .into());
+                }
+                Ok(LaunchState::Verifying)
+            }
+            LaunchState::Verifying => {
+                let deadline = self.clock.now() + timeout;
+                while self.clock.now() < deadline {
+                    if !self.game_running(request.game)? {
+                        return Err(format!(
+                            "{} exited right after starting",
+                            request.game.display_name
+                        )
+                        .into());
+                    }
+                    self.pause(self.policy.poll_interval())?;
+                }
+                Ok(LaunchState::Done)
+            }
+            LaunchState::Done | LaunchState::Failed | LaunchState::Cancelled => Ok(state),
+        }
+    }
+
+    // Every input call is preceded by a cancellation check, so once cancel_launch
+    // returns nothing else is typed. FocusGuard stops it if another window takes focus.
+    fn log_in(&self, window: WindowHandle, request: &LaunchRequest) -> Result<(), StepError> {
+        info!("Starting login sequence");
+        let windows = self.platform.windows.as_ref();
+
+        let target =
+            field_location::locator(request.field_location).locate(windows.rect(window))?;
+
+        self.check_cancelled()?;
+        windows.focus(window);
+        self.pause(Duration::from_millis(500))?;
+
+        let input = FocusGuard::new(windows, self.platform.input.as_ref(), window);
+        let input_error = |e: String| {
+            if input.lost_focus() {
+                StepError::FocusLost
+            } else {
+                StepError::Other(e)
+            }
+        };
+        match target {
+            FieldTarget::Click { x, y } => {
+                self.check_cancelled()?;
+                input.move_mouse(x, y).map_err(input_error)?;
+                self.pause(Duration::from_millis(50))?;
+                self.check_cancelled()?;
+                input.left_click().map_err(input_error)?;
+            }
+            FieldTarget::Keyboard { tabs } => {
+                for _ in 0..tabs {
+                    self.check_cancelled()?;
+                    input.key(InputKey::Tab).map_err(input_error)?;
+                    self.pause(Duration::from_millis(50))?;
+                }
+            }
+        }
+        self.pause(Duration::from_millis(200))?;
+
+        let entry = credential_entry::strategy(request.entry_mode, self.platform);
+        let pause = |duration| self.pause(duration).map_err(|e| e.to_string());
+        self.check_cancelled()?;
+        entry
+            .enter(&input, request.username, &pause)
+            .map_err(input_error)?;
+        self.check_cancelled()?;
+        input.key(InputKey::Tab).map_err(input_error)?;
+        self.pause(Duration::from_millis(100))?;
+
+        self.check_cancelled()?;
+        entry
+            .enter(&input, request.password, &pause)
+            .map_err(input_error)?;
+        self.check_cancelled()?;
+        input.key(InputKey::Return).map_err(input_error)?;
+        self.pause(Duration::from_secs(1))?;
+
+        info!("Login complete, waiting for client to be ready...");
+        let timeout = self.policy.logging_in.timeout();
+        match self.wait_for_api(timeout, || Ok(self.api.session()?.is_some()))? {
+            Some(true) => Ok(()),
+            Some(false) => {
+                Err("Riot Client did not sign in. Check the username and password.".into())
+            }
+            None => self.pause(Duration::from_millis(1500)),
+        }
+    }
+
+    // Polls the local client API until `check` passes. None means the API could not
+    // be reached, in which case callers fall back to the old fixed waits.
+    fn wait_for_api(
+        &self,
+        timeout: Duration,
+        check: impl Fn() -> Result<bool, String>,
+    ) -> Result<Option<bool>, StepError> {
+        let mut unavailable = false;
+        let passed = self.wait_until(timeout, || match check() {
+            Ok(passed) => Ok(passed),
+            Err(e) => {
+                warn!("Riot Client API unavailable: {}", e);
+                unavailable = true;
+                Ok(true)
+            }
+        })?;
+        Ok((!unavailable).then_some(passed))
+    }
+
+    fn check_cancelled(&self) -> Result<(), StepError> {
+        if self.cancel.is_cancelled() {
+            Err(StepError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Sleeps in poll-interval slices so a cancel is noticed within one interval.
+    fn pause(&self, duration: Duration) -> Result<(), StepError> {
+        let deadline = self.clock.now() + duration;
+        loop {
+            self.check_cancelled()?;
+            let now = self.clock.now();
+            if now >= deadline {
+                return Ok(());
+            }
+            self.clock
+                .sleep((deadline - now).min(self.policy.poll_interval()));
+        }
+    }
+
+    fn wait_until(
+        &self,
+        timeout: Duration,
+        mut check: impl FnMut() -> Result<bool, StepError>,
+    ) -> Result<bool, StepError> {
+        let deadline = self.clock.now() + timeout;
+        loop {
+            self.check_cancelled()?;
+            if check()? {
+                return Ok(true);
+            }
+            if self.clock.now() >= deadline {
+                return Ok(false);
+            }
+            self.pause(self.policy.poll_interval())?;
+        }
+    }
+
+    fn client_running(&self) -> Result<bool, StepError> {
+        Ok(self.platform.processes.list()?.iter().any(|process| {
+            RIOT_CLIENT_PROCESSES
+                .iter()
+                .any(|name| process.is_named(name))
+        }))
+    }
+
+    fn game_running(&self, game: &GameDefinition) -> Result<bool, StepError> {
+        Ok(self
+            .platform
+            .processes
+            .list()?
+            .iter()
+            .any(|process| game.is_game_process(process)))
+    }
+
+    // Leftovers of a failed attempt are closed before launching again. The Riot
+    // Client stays up since we are still signed in through it.
+    fn close_game(&self, game: &GameDefinition) {
+        let running = match self.platform.processes.list() {
+            Ok(running) => running,
+            Err(e) => {
+                warn!("Failed to list processes: {}", e);
+                return;
+            }
+        };
+        let targets =
+            shutdown::select_targets(&running, game.close_list(), RIOT_CLIENT_PROCESSES, true);
+        let options = CloseOptions {
+            poll_interval: self.policy.poll_interval(),
+            ..CloseOptions::default()
+        };
+        if let Err(e) = shutdown::close_tree(
+            self.platform.processes.as_ref(),
+            self.platform.control.as_ref(),
+            &targets,
+            &options,
+            &|duration| self.pause(duration).map_err(|e| e.to_string()),
+        ) {
+            warn!("Failed to close {}: {}", game.display_name, e);
+        }
+    }
+}
+
+fn progress(
+    request: &LaunchRequest,
+    state: LaunchState,
+    attempt: u32,
+    message: Option<String>,
+) -> LaunchProgress {
+    LaunchProgress {
+        game: request.game.id.clone(),
+        state,
+        attempt,
+        message,
+        percent: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::GameRegistry;
+    use crate::idle::FakeClock;
+    use crate::patch_status::PatchState;
+    use crate::platform::fake::{process, Action, FakePlatform, FakeState, FakeWindow};
+    use crate::platform::WindowRect;
+    use crate::riot_api::SignedInAccount;
+    use std::sync::Mutex;
+
+    const CLIENT: &str = "C:\\Riot Games\\Riot Client\\RiotClientServices.exe";
+
+    type Session = Result<Option<SignedInAccount>, String>;
+    type Patch = Option<(PatchState, Option<f32>)>;
+
+    // Answers from a script, one entry per call, repeating the last entry.
+    struct FakeApi {
+        sessions: Mutex<Vec<Session>>,
+        patches: Mutex<Vec<Patch>>,
+        sign_outs: Mutex<u32>,
+    }
+
+    impl FakeApi {
+        fn new(sessions: Vec<Session>) -> Self {
+            FakeApi {
+                sessions: Mutex::new(sessions),
+                patches: Mutex::new(vec![Some((PatchState::UpToDate, None))]),
+                sign_outs: Mutex::new(0),
+            }
+        }
+    }
+
+    fn next<T: Clone>(script: &Mutex<Vec<T>>) -> T {
+        let mut script = script.lock().unwrap();
+        if script.len() > 1 {
+            script.remove(0)
+        } else {
+            script[0].clone()
+        }
+    }
+
+    impl RiotClientApi for FakeApi {
+        fn session(&self) -> Result<Option<SignedInAccount>, String> {
+            next(&self.sessions)
+        }
+
+        fn is_product_ready(&self, _product_id: &str, _patchline: &str) -> Result<bool, String> {
+            Ok(true)
+        }
+
+        fn sign_out(&self) -> Result<(), String> {
+            *self.sign_outs.lock().unwrap() += 1;
+            Ok(())
+        }
+
+        fn patch_state(&self, _product_id: &str, _patchline: &str) -> Result<Patch, String> {
+            Ok(next(&self.patches))
+        }
+    }
+
+    fn signed_in(username: &str) -> Session {
+        Ok(Some(SignedInAccount {
+            puuid: format!("puuid-{}", username),
+            username: Some(username.to_string()),
+            game_name: None,
+            tag_line: None,
+        }))
+    }
+
+    fn client_window() -> FakeWindow {
+        FakeWindow {
+            handle: WindowHandle(1),
+            class: "Chrome_WidgetWin_1".to_string(),
+            title: "Riot Client".to_string(),
+            rect: WindowRect {
+                left: 0,
+                top: 0,
+                right: 1280,
+                bottom: 720,
+            },
+            pid: 10,
+            minimized: false,
+        }
+    }
+
+    // The client comes up with its window on the first spawn, the game on the next.
+    fn start_client_then_game(state: &mut FakeState) {
+        match state.actions.last() {
+            Some(Action::Spawn(_, args)) if args.is_empty() => {
+                state
+                    .processes
+                    .push(process(10, "RiotClientServices.exe", None));
+                state.windows.push(client_window());
+            }
+            _ => state
+                .processes
+                .push(process(20, "LeagueClient.exe", Some(10))),
+        }
+    }
+
+    fn riot_client() -> FakePlatform {
+        FakePlatform::new(FakeState {
+            on_spawn: Some(Box::new(start_client_then_game)),
+            ..FakeState::default()
+        })
+    }
+
+    fn launch(
+        fake: &FakePlatform,
+        api: &FakeApi,
+    ) -> (Result<(), NidaleeError>, Vec<LaunchProgress>) {
+        launch_until(fake, api, CancelToken::default())
+    }
+
+    fn launch_until(
+        fake: &FakePlatform,
+        api: &FakeApi,
+        cancel: CancelToken,
+    ) -> (Result<(), NidaleeError>, Vec<LaunchProgress>) {
+        let games = GameRegistry::builtin();
+        let location = FieldLocation::default();
+        let request = LaunchRequest {
+            game: games.get("league").unwrap(),
+            patchline: "live",
+            riot_client_path: CLIENT,
+            username: "player",
+            password: "hunter2",
+            login_delay: Duration::from_secs(2),
+            entry_mode: EntryMode::Typing,
+            field_location: &location,
+        };
+        let platform = fake.platform();
+        let clock = FakeClock::new();
+        let policy = LaunchPolicy::default();
+        let launcher = Launcher::new(&platform, api, &clock, &policy, cancel);
+
+        let mut updates = Vec::new();
+        let result = launcher.run(&request, &mut |update| updates.push(update));
+        (result, updates)
+    }
+
+    fn states(updates: &[LaunchProgress]) -> Vec<(LaunchState, u32)> {
+        updates
+            .iter()
+            .filter(|update| update.percent.is_none())
+            .map(|update| (update.state, update.attempt))
+            .collect()
+    }
+
+    fn typed(fake: &FakePlatform) -> String {
+        fake.actions()
+            .into_iter()
+            .filter_map(|action| match action {
+                Action::Type(text) => Some(text),
+                Action::Key(InputKey::Tab) => Some("<tab>".to_string()),
+                Action::Key(InputKey::Return) => Some("<return>".to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn spawns(fake: &FakePlatform) -> Vec<Action> {
+        fake.actions()
+            .into_iter()
+            .filter(|action| matches!(action, Action::Spawn(..)))
+            .collect()
+    }
+
+    #[test]
+    fn retries_start_over_where_the_failure_began() {
+        assert_eq!(
+            retry_from(LaunchState::WaitingForWindow),
+            LaunchState::SpawningClient
+        );
+        assert_eq!(
+            retry_from(LaunchState::Verifying),
+            LaunchState::LaunchingGame
+        );
+        assert_eq!(retry_from(LaunchState::LoggingIn), LaunchState::LoggingIn);
+        assert_eq!(
+            retry_from(LaunchState::LaunchingGame),
+            LaunchState::LaunchingGame
+        );
+    }
+
+    #[test]
+    fn logs_in_and_launches_the_game() {
+        let fake = riot_client();
+        let api = FakeApi::new(vec![Ok(None), signed_in("player")]);
+        let (result, updates) = launch(&fake, &api);
+
+        result.unwrap();
+        assert_eq!(
+            states(&updates),
+            [
+                (LaunchState::SpawningClient, 1),
+                (LaunchState::WaitingForWindow, 1),
+                (LaunchState::WaitingForUpdate, 1),
+                (LaunchState::LoggingIn, 1),
+                (LaunchState::LaunchingGame, 1),
+                (LaunchState::Verifying, 1),
+                (LaunchState::Done, 1),
+            ]
+        );
+        assert_eq!(typed(&fake), "player<tab>hunter2<return>");
+        assert_eq!(
+            spawns(&fake),
+            [
+                Action::Spawn(CLIENT.to_string(), vec![]),
+                Action::Spawn(
+                    CLIENT.to_string(),
+                    vec![
+                        "--launch-product=league_of_legends".to_string(),
+                        "--launch-patchline=live".to_string(),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_the_login_when_already_signed_in() {
+        let fake = riot_client();
+        let api = FakeApi::new(vec![signed_in("Player")]);
+        let (result, updates) = launch(&fake, &api);
+
+        result.unwrap();
+        assert!(!states(&updates).contains(&(LaunchState::SigningOut, 1)));
+        assert_eq!(typed(&fake), "");
+        assert_eq!(updates.last().unwrap().state, LaunchState::Done);
+    }
+
+    #[test]
+    fn signs_out_another_account_first() {
+        let fake = riot_client();
+        let api = FakeApi::new(vec![
+            signed_in("someone_else"),
+            Ok(None),
+            Ok(None),
+            signed_in("player"),
+        ]);
+        let (result, updates) = launch(&fake, &api);
+
+        result.unwrap();
+        assert_eq!(*api.sign_outs.lock().unwrap(), 1);
+        assert_eq!(
+            states(&updates)[3..6],
+            [
+                (LaunchState::LoggingIn, 1),
+                (LaunchState::SigningOut, 1),
+                (LaunchState::LoggingIn, 2),
+            ]
+        );
+        assert_eq!(typed(&fake), "player<tab>hunter2<return>");
+    }
+
+    #[test]
+    fn stops_for_good_when_the_client_loses_focus() {
+        let fake = riot_client();
+        // Focus is lost while the username is being typed.
+        fake.state.lock().unwrap().steal_focus_after = Some(4);
+        let api = FakeApi::new(vec![Ok(None)]);
+        let (result, updates) = launch(&fake, &api);
+
+        assert_eq!(result.unwrap_err(), NidaleeError::FocusLost);
+        assert_eq!(typed(&fake), "pl");
+        assert!(!states(&updates).contains(&(LaunchState::LoggingIn, 2)));
+    }
+
+    #[test]
+    fn stops_without_typing_once_cancelled() {
+        let fake = riot_client();
+        let cancel = CancelToken::default();
+        let cancel_on_spawn = cancel.clone();
+        // The user cancels as soon as the client has been started.
+        fake.state.lock().unwrap().on_spawn = Some(Box::new(move |state: &mut FakeState| {
+            start_client_then_game(state);
+            cancel_on_spawn.cancel();
+        }));
+        let api = FakeApi::new(vec![Ok(None)]);
+        let (result, updates) = launch_until(&fake, &api, cancel);
+
+        assert_eq!(result.unwrap_err(), NidaleeError::LaunchCancelled);
+        assert_eq!(updates.last().unwrap().state, LaunchState::Cancelled);
+        assert_eq!(typed(&fake), "");
+    }
+
+    #[test]
+    fn types_after_the_wait_when_the_api_is_unavailable() {
+        let fake = riot_client();
+        let api = FakeApi::new(vec![Err("Riot Client API is not reachable".to_string())]);
+        let (result, updates) = launch(&fake, &api);
+
+        result.unwrap();
+        assert_eq!(*api.sign_outs.lock().unwrap(), 0);
+        assert!(!states(&updates).contains(&(LaunchState::LoggingIn, 2)));
+        assert_eq!(typed(&fake), "player<tab>hunter2<return>");
+    }
+
+    #[test]
+    fn waits_for_the_session_to_become_readable() {
+        let fake = riot_client();
+        let api = FakeApi::new(vec![
+            Err("Riot Client API is not reachable".to_string()),
+            Err("Riot Client API is not reachable".to_string()),
+            Ok(None),
+            signed_in("player"),
+        ]);
+        let (result, _) = launch(&fake, &api);
+
+        result.unwrap();
+        assert_eq!(typed(&fake), "player<tab>hunter2<return>");
+    }
+
+    #[test]
+    fn gives_up_when_the_old_account_keeps_coming_back() {
+        let fake = riot_client();
+        let api = FakeApi::new(vec![
+            signed_in("someone_else"),
+            Ok(None),
+            signed_in("someone_else"),
+            Ok(None),
+            signed_in("someone_else"),
+        ]);
+        let (result, updates) = launch(&fake, &api);
+
+        assert_eq!(
+            result.unwrap_err(),
+            NidaleeError::LaunchFailed {
+                attempts: 1,
+                reason: SIGN_OUT_LOOP.to_string(),
+            }
+        );
+        assert_eq!(*api.sign_outs.lock().unwrap(), MAX_SIGN_OUTS);
+        assert!(states(&updates).contains(&(LaunchState::LoggingIn, 3)));
+        assert_eq!(typed(&fake), "");
+    }
+
+    #[test]
+    fn respawns_the_client_when_its_window_never_shows() {
+        let fake = riot_client();
+        let mut spawned = 0;
+        fake.state.lock().unwrap().on_spawn = Some(Box::new(move |state: &mut FakeState| {
+            spawned += 1;
+            // The first client start leaves a process without a window behind.
+            if spawned == 1 {
+                state
+                    .processes
+                    .push(process(10, "RiotClientServices.exe", None));
+            } else {
+                start_client_then_game(state);
+            }
+        }));
+        let api = FakeApi::new(vec![signed_in("player")]);
+        let (result, updates) = launch(&fake, &api);
+
+        result.unwrap();
+        assert_eq!(
+            states(&updates)[..4],
+            [
+                (LaunchState::SpawningClient, 1),
+                (LaunchState::WaitingForWindow, 1),
+                (LaunchState::SpawningClient, 2),
+                (LaunchState::WaitingForWindow, 2),
+            ]
+        );
+        assert_eq!(
+            updates[2].message.as_deref(),
+            Some("Riot Client window not found after 30 seconds")
+        );
+    }
+
+    #[test]
+    fn closes_leftovers_before_launching_the_game_again() {
+        let fake = riot_client();
+        let mut game_spawns = 0;
+        fake.state.lock().unwrap().on_spawn = Some(Box::new(move |state: &mut FakeState| {
+            match state.actions.last() {
+                Some(Action::Spawn(_, args)) if !args.is_empty() => {
+                    game_spawns += 1;
+                    // The first attempt only gets as far as the renderer.
+                    if game_spawns == 1 {
+                        state
+                            .processes
+                            .push(process(30, "LeagueClientUxRender.exe", Some(10)));
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            start_client_then_game(state);
+        }));
+        let api = FakeApi::new(vec![signed_in("player")]);
+        let (result, updates) = launch(&fake, &api);
+
+        result.unwrap();
+        assert!(states(&updates).contains(&(LaunchState::LaunchingGame, 2)));
+        assert!(fake.actions().contains(&Action::RequestClose(30)));
+        assert_eq!(fake.pids(), [10, 20]);
+    }
+
+    #[test]
+    fn fails_once_the_retries_are_used_up() {
+        let fake = riot_client();
+        fake.state.lock().unwrap().on_spawn = Some(Box::new(|state: &mut FakeState| {
+            // The game never starts.
+            if state.processes.is_empty() {
+                start_client_then_game(state);
+            }
+        }));
+        let api = FakeApi::new(vec![signed_in("player")]);
+        let (result, updates) = launch(&fake, &api);
+
+        let reason = "League of Legends did not start within 8 seconds".to_string();
+        assert_eq!(
+            result.unwrap_err(),
+            NidaleeError::LaunchFailed {
+                attempts: 5,
+                reason: reason.clone(),
+            }
+        );
+        let states = states(&updates);
+        assert_eq!(states[states.len() - 2], (LaunchState::LaunchingGame, 5));
+        let failed = updates.last().unwrap();
+        assert_eq!(failed.state, LaunchState::Failed);
+        assert_eq!(failed.message, Some(reason));
+    }
+
+    #[test]
+    fn times_out_waiting_for_an_update() {
+        let fake = riot_client();
+        let api = FakeApi::new(vec![signed_in("player")]);
+        *api.patches.lock().unwrap() = vec![
+            Some((PatchState::Updating, Some(10.0))),
+            Some((PatchState::Updating, Some(42.0))),
+        ];
+        let (result, updates) = launch(&fake, &api);
+
+        assert_eq!(result.unwrap_err(), NidaleeError::UpdateTimeout);
+        let percents: Vec<f32> = updates.iter().filter_map(|update| update.percent).collect();
+        assert_eq!(percents, [10.0, 42.0]);
+        let failed = updates.last().unwrap();
+        assert_eq!(failed.state, LaunchState::Failed);
+        assert_eq!(
+            failed.message.as_deref(),
+            Some("Game update is taking too long. Please try again later.")
+        );
+        assert_eq!(spawns(&fake).len(), 1);
+    }
+}
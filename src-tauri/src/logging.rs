@@ -0,0 +1,412 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::hash::BuildHasher;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tracing::field::Field;
+use tracing_subscriber::filter::EnvFilter;
+use tracing_subscriber::fmt::format::{debug_fn, Writer};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{reload, Registry};
+
+pub const LOG_DIR: &str = "logs";
+pub const LOG_FILE: &str = "nidalee.log";
+pub const DEFAULT_LEVEL: &str = "info";
+// Overrides the level from the settings, e.g. NIDALEE_LOG=nidalee=debug.
+const LEVEL_ENV: &str = "NIDALEE_LOG";
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const KEEP_FILES: usize = 3;
+const REDACTED: &str = "[redacted]";
+
+// Fields with these names are never written, whatever their value.
+const SECRET_FIELDS: &[&str] = &["password", "current_password", "new_password"];
+const USERNAME_FIELDS: &[&str] = &["username"];
+
+struct Secret {
+    len: usize,
+    hash: u64,
+    username: bool,
+}
+
+// Scrubs known account passwords and usernames out of every formatted line.
+// Only a keyed hash of each value is kept, so locking the vault still leaves no
+// plaintext credentials in memory.
+pub struct Redactor {
+    keys: OnceLock<RandomState>,
+    secrets: Mutex<Vec<Secret>>,
+    log_usernames: AtomicBool,
+}
+
+pub static REDACTOR: Redactor = Redactor::new();
+
+impl Redactor {
+    pub const fn new() -> Self {
+        Redactor {
+            keys: OnceLock::new(),
+            secrets: Mutex::new(Vec::new()),
+            log_usernames: AtomicBool::new(false),
+        }
+    }
+
+    fn hash(&self, bytes: &[u8]) -> u64 {
+        self.keys.get_or_init(RandomState::new).hash_one(bytes)
+    }
+
+    fn add(&self, value: &str, username: bool) {
+        if value.is_empty() {
+            return;
+        }
+        // Riot usernames are case-insensitive, so they are matched that way too.
+        let hash = if username {
+            self.hash(value.to_ascii_lowercase().as_bytes())
+        } else {
+            self.hash(value.as_bytes())
+        };
+        let mut secrets = self.secrets.lock().unwrap();
+        let known = secrets
+            .iter()
+            .any(|s| s.len == value.len() && s.hash == hash && s.username == username);
+        if !known {
+            secrets.push(Secret {
+                len: value.len(),
+                hash,
+                username,
+            });
+        }
+    }
+
+    pub fn register(&self, username: &str, password: &str) {
+        self.add(password, false);
+        self.add(username, true);
+    }
+
+    pub fn set_log_usernames(&self, enabled: bool) {
+        self.log_usernames.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn logs_usernames(&self) -> bool {
+        self.log_usernames.load(Ordering::Relaxed)
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let log_usernames = self.logs_usernames();
+        let secrets = self.secrets.lock().unwrap();
+        let active: Vec<&Secret> = secrets
+            .iter()
+            .filter(|s| !(s.username && log_usernames))
+            .collect();
+        if active.is_empty() {
+            return text.to_string();
+        }
+        let mut lengths: Vec<usize> = active.iter().map(|s| s.len).collect();
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        lengths.dedup();
+
+        let bytes = text.as_bytes();
+        let mut out = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let matched = lengths.iter().copied().find(|&len| {
+                let end = i + len;
+                if end > bytes.len() || !text.is_char_boundary(end) {
+                    return false;
+                }
+                let window = &bytes[i..end];
+                let exact = self.hash(window);
+                let folded = self.hash(&window.to_ascii_lowercase());
+                active
+                    .iter()
+                    .any(|s| s.len == len && s.hash == if s.username { folded } else { exact })
+            });
+            match matched {
+                Some(len) => {
+                    out.push_str(REDACTED);
+                    i += len;
+                }
+                None => {
+                    let c = text[i..].chars().next().unwrap();
+                    out.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+        out
+    }
+}
+
+pub fn format_field(writer: &mut Writer<'_>, field: &Field, value: &dyn fmt::Debug) -> fmt::Result {
+    let name = field.name();
+    let hidden = SECRET_FIELDS.contains(&name)
+        || (USERNAME_FIELDS.contains(&name) && !REDACTOR.logs_usernames());
+    match (name, hidden) {
+        (_, true) => write!(writer, "{}={}", name, REDACTED),
+        ("message", false) => write!(writer, "{:?}", value),
+        _ => write!(writer, "{}={:?}", name, value),
+    }
+}
+
+// Wraps any writer so every event is scrubbed by the redactor before it is written.
+pub struct Redacting<M> {
+    inner: M,
+    redactor: &'static Redactor,
+}
+
+impl<M> Redacting<M> {
+    pub fn new(inner: M, redactor: &'static Redactor) -> Self {
+        Redacting { inner, redactor }
+    }
+}
+
+pub struct RedactingWriter<W: Write> {
+    inner: W,
+    redactor: &'static Redactor,
+    buffer: Vec<u8>,
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            inner: self.inner.make_writer(),
+            redactor: self.redactor,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let line = self.redactor.redact(&String::from_utf8_lossy(&self.buffer));
+        self.buffer.clear();
+        self.inner.write_all(line.as_bytes())?;
+        self.inner.flush()
+    }
+}
+
+// A whole event is buffered, so a secret can't be split across two writes.
+impl<W: Write> Drop for RedactingWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+// nidalee.log is rolled over to nidalee.log.1 once it grows past the limit,
+// keeping a fixed number of older files.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    state: Mutex<(File, u64)>,
+}
+
+impl RotatingFile {
+    pub fn new(path: PathBuf, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            max_bytes,
+            keep,
+            state: Mutex::new((file, size)),
+        })
+    }
+
+    fn backup_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&self) -> io::Result<File> {
+        if self.keep == 0 {
+            return OpenOptions::new()
+                .write(true)
+                .truncate(true)
+                .create(true)
+                .open(&self.path);
+        }
+        let _ = fs::remove_file(self.backup_path(self.keep));
+        for index in (1..self.keep).rev() {
+            let from = self.backup_path(index);
+            if from.exists() {
+                fs::rename(&from, self.backup_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.backup_path(1))?;
+        open_append(&self.path)
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().append(true).create(true).open(path)
+}
+
+impl Write for &RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        if state.1 > 0 && state.1 + buf.len() as u64 > self.max_bytes {
+            state.0 = self.rotate()?;
+            state.1 = 0;
+        }
+        let written = state.0.write(buf)?;
+        state.1 += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.state.lock().unwrap().0.flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for RotatingFile {
+    type Writer = &'a RotatingFile;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
+
+static LEVEL: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+fn filter_for(level: &str) -> EnvFilter {
+    if let Ok(filter) = EnvFilter::try_from_env(LEVEL_ENV) {
+        return filter;
+    }
+    EnvFilter::try_new(level).unwrap_or_else(|_| EnvFilter::new(DEFAULT_LEVEL))
+}
+
+// Logs go to the rotating file and, for debug builds run from a console, stdout.
+// Starts at the default level until the settings are loaded.
+pub fn init(app_data_dir: &Path) -> Result<PathBuf, String> {
+    let log_dir = app_data_dir.join(LOG_DIR);
+    fs::create_dir_all(&log_dir).map_err(|e| e.to_string())?;
+    let log_path = log_dir.join(LOG_FILE);
+    let file = RotatingFile::new(log_path.clone(), MAX_FILE_BYTES, KEEP_FILES)
+        .map_err(|e| e.to_string())?;
+
+    let (filter, handle) = reload::Layer::new(filter_for(DEFAULT_LEVEL));
+    let subscriber = tracing_subscriber::registry().with(filter).with(
+        tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .fmt_fields(debug_fn(format_field).delimited(" "))
+            .with_writer(Redacting::new(file, &REDACTOR)),
+    );
+    #[cfg(debug_assertions)]
+    let subscriber = subscriber.with(
+        tracing_subscriber::fmt::layer()
+            .fmt_fields(debug_fn(format_field).delimited(" "))
+            .with_writer(Redacting::new(io::stdout, &REDACTOR)),
+    );
+    subscriber.try_init().map_err(|e| e.to_string())?;
+    let _ = LEVEL.set(handle);
+    Ok(log_path)
+}
+
+pub fn set_level(level: &str) {
+    if let Some(handle) = LEVEL.get() {
+        if let Err(e) = handle.reload(filter_for(level)) {
+            tracing::warn!("Failed to change log level: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tracing::{debug, info, warn};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    // Logs through the same layer setup as init, into a buffer instead of a file.
+    fn capture(redactor: &'static Redactor, log: impl FnOnce()) -> String {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .fmt_fields(debug_fn(format_field).delimited(" "))
+                .with_writer(Redacting::new(buffer.clone(), redactor)),
+        );
+        tracing::subscriber::with_default(subscriber, log);
+        let output = buffer.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn never_writes_passwords_or_usernames() {
+        static REDACTOR: Redactor = Redactor::new();
+        REDACTOR.register("SmurfKing", "hunter2!");
+
+        let output = capture(&REDACTOR, || {
+            info!("Logging in as {}", "SmurfKing");
+            info!(username = "someone-else", "Typing the username");
+            debug!(password = "not-registered", "Typing the password");
+            warn!("Riot Client rejected smurfking with hunter2!, retrying");
+            let error = format!("Could not type {:?}", "hunter2!");
+            warn!("Login failed: {}", error);
+        });
+
+        assert!(output.contains("Logging in as [redacted]"), "{}", output);
+        assert!(output.contains("username=[redacted]"), "{}", output);
+        assert!(output.contains("password=[redacted]"), "{}", output);
+        for secret in ["smurfking", "hunter2", "someone-else", "not-registered"] {
+            assert!(
+                !output.to_ascii_lowercase().contains(secret),
+                "{} leaked: {}",
+                secret,
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn usernames_can_be_logged_but_passwords_never() {
+        static REDACTOR: Redactor = Redactor::new();
+        REDACTOR.register("SmurfKing", "hunter2!");
+        REDACTOR.set_log_usernames(true);
+
+        let output = capture(&REDACTOR, || {
+            info!("Logging in as {} with {}", "SmurfKing", "hunter2!");
+        });
+
+        assert!(
+            output.contains("Logging in as SmurfKing with [redacted]"),
+            "{}",
+            output
+        );
+        assert!(!output.contains("hunter2"), "{}", output);
+    }
+}
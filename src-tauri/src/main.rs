@@ -4,34 +4,59 @@
 )]
 
 use chrono::Utc;
-use enigo::{Enigo, Key, KeyboardControllable, MouseButton, MouseControllable};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::fs::create_dir_all;
-use std::fs::read_dir;
 use std::path::Path;
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
 use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::api::dialog::blocking::MessageDialogBuilder;
+use tauri::api::dialog::MessageDialogKind;
 use tauri::api::path;
 use tauri::SystemTrayMenuItem;
 use tauri::{CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu};
-use windows::core::PCSTR;
-use windows::core::PCWSTR;
-use windows::Win32::Foundation::{HWND, RECT};
-use windows::Win32::System::Threading::{CreateMutexW, OpenMutexW, MUTEX_ALL_ACCESS};
-use windows::Win32::UI::WindowsAndMessaging::{FindWindowA, GetWindowRect, BringWindowToTop, SetForegroundWindow, GetWindowTextA};
-use winreg::enums::*;
-use winreg::RegKey;
 
-#[cfg(target_os = "windows")]
-use std::os::windows::process::CommandExt;
+mod accounts;
+mod client_search;
+mod credential_entry;
+mod db;
+mod error;
+mod field_location;
+mod games;
+mod idle;
+mod installs;
+mod launch;
+mod logging;
+mod migrations;
+mod patch_status;
+mod platform;
+mod riot_api;
+mod shutdown;
+mod storage;
+mod store;
+mod vault;
+mod watcher;
+
+use accounts::{AccountVault, VaultStatus};
+use client_search::{SearchLimits, SearchOutcome, SearchProgress, CLIENT_EXECUTABLE};
+use credential_entry::EntryMode;
+use error::NidaleeError;
+use field_location::FieldLocation;
+use games::{GameDefinition, GameRegistry, GameStatus};
+use idle::{IdleTimer, SystemClock};
+use installs::GameInstall;
+use launch::{CancelToken, LaunchPolicy, LaunchRequest, Launcher};
+use platform::{Hive, Platform, Registry};
+use riot_api::{LocalApi, RiotClientApi, SignedInAccount};
+use shutdown::{CloseOptions, CloseReport};
+use store::{SqliteStore, Store};
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct Account {
@@ -39,9 +64,14 @@ struct Account {
     name: String,
     username: String,
     password: String,
+    email: Option<String>,
     category: String,
     last_login: Option<String>,
+    #[serde(default)]
+    login_count: u32,
     game_type: String,
+    #[serde(default)]
+    patchline: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -54,374 +84,290 @@ pub struct Settings {
     pub minimize_on_game_launch: bool,
     pub login_delay: u32,
     pub preferred_monitor: Option<usize>,
+    #[serde(default = "default_auto_lock_minutes")]
+    pub auto_lock_minutes: u32,
+    #[serde(default)]
+    pub launch: LaunchPolicy,
+    #[serde(default)]
+    pub credential_entry: EntryMode,
+    #[serde(default)]
+    pub field_location: FieldLocation,
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    // Account usernames are redacted from the log unless this is set.
+    #[serde(default)]
+    pub log_usernames: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct Categories {
-    categories: Vec<String>,
+fn default_auto_lock_minutes() -> u32 {
+    15
+}
+
+fn default_log_level() -> String {
+    logging::DEFAULT_LEVEL.to_string()
+}
+
+fn apply_log_settings(settings: &Settings) {
+    logging::REDACTOR.set_log_usernames(settings.log_usernames);
+    logging::set_level(&settings.log_level);
 }
 
 struct AppState {
-    accounts: Mutex<HashMap<String, Account>>,
+    store: Mutex<Box<dyn Store>>,
+    vault: AccountVault,
+    idle: IdleTimer<SystemClock>,
+    launch_in_progress: AtomicBool,
+    launch_cancel: Mutex<CancelToken>,
+    install_scan: Mutex<CancelToken>,
+    lock_pending: AtomicBool,
     settings: Mutex<Settings>,
     last_move_time: AtomicU64,
     last_monitor: Mutex<Option<usize>>,
+    platform: Platform,
+    riot_api: Box<dyn RiotClientApi>,
+    games: GameRegistry,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct GameStatus {
-    league_running: bool,
-    valorant_running: bool,
+#[tauri::command]
+async fn save_account(
+    account: Account,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), NidaleeError> {
+    state.idle.touch();
+    state.vault.save(&state.store, account)
 }
 
 #[tauri::command]
-async fn save_account(account: Account, state: tauri::State<'_, AppState>) -> Result<(), String> {
-    let mut accounts = state.accounts.lock().unwrap();
-    accounts.insert(account.id.clone(), account);
+async fn get_accounts(state: tauri::State<'_, AppState>) -> Result<Vec<Account>, NidaleeError> {
+    state.idle.touch();
+    state.vault.accounts()
+}
 
-    let accounts_path = get_app_data_dir()?.join("accounts.json");
-    let accounts_json = serde_json::to_string_pretty(&*accounts).map_err(|e| e.to_string())?;
-    fs::write(accounts_path, accounts_json).map_err(|e| e.to_string())?;
+#[tauri::command]
+async fn delete_account(id: String, state: tauri::State<'_, AppState>) -> Result<(), NidaleeError> {
+    state.idle.touch();
+    state.vault.delete(&state.store, &id)
+}
 
+#[tauri::command]
+async fn get_vault_status(state: tauri::State<'_, AppState>) -> Result<VaultStatus, NidaleeError> {
+    Ok(state.vault.status())
+}
+
+#[tauri::command]
+async fn unlock_vault(
+    password: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), NidaleeError> {
+    state.vault.unlock(&state.store, &password)?;
+    state.idle.touch();
     Ok(())
 }
 
 #[tauri::command]
-async fn get_accounts(state: tauri::State<'_, AppState>) -> Result<Vec<Account>, String> {
-    let accounts = state.accounts.lock().unwrap();
-    Ok(accounts.values().cloned().collect())
+async fn lock_vault(
+    window: tauri::Window,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), NidaleeError> {
+    state.vault.check_lockable()?;
+    if lock_if_protected(&state) {
+        let _ = window.emit("vault-locked", ());
+    }
+    Ok(())
 }
 
 #[tauri::command]
-async fn delete_account(id: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
-    let mut accounts = state.accounts.lock().unwrap();
-    accounts.remove(&id);
+async fn change_master_password(
+    current_password: Option<String>,
+    new_password: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), NidaleeError> {
+    state.idle.touch();
+    state
+        .vault
+        .change_password(&state.store, current_password.as_deref(), &new_password)?;
+
+    let key_file = get_app_data_dir()?.join("vault.key");
+    if key_file.exists() {
+        let _ = fs::remove_file(key_file);
+    }
+    Ok(())
+}
+
+// Locks are deferred while a launch is typing credentials and applied once it finishes.
+fn lock_if_protected(state: &AppState) -> bool {
+    if state.launch_in_progress.load(Ordering::SeqCst) {
+        state.lock_pending.store(true, Ordering::SeqCst);
+        return false;
+    }
+    state.vault.lock()
+}
 
-    let accounts_path = get_app_data_dir()?.join("accounts.json");
-    let accounts_json = serde_json::to_string(&*accounts).map_err(|e| e.to_string())?;
-    fs::write(accounts_path, accounts_json).map_err(|e| e.to_string())?;
+struct LaunchGuard<'a> {
+    state: &'a AppState,
+    window: tauri::Window,
+    cancel: CancelToken,
+}
 
-    Ok(())
+impl<'a> LaunchGuard<'a> {
+    // Only one launch runs at a time. The cancel token is swapped while its lock is
+    // held, so cancel_launch never reaches the previous launch's token.
+    fn new(state: &'a AppState, window: tauri::Window) -> Result<Self, NidaleeError> {
+        let cancel = CancelToken::default();
+        let mut current = state.launch_cancel.lock().unwrap();
+        state
+            .launch_in_progress
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .map_err(|_| NidaleeError::LaunchInProgress)?;
+        *current = cancel.clone();
+        Ok(LaunchGuard {
+            state,
+            window,
+            cancel,
+        })
+    }
+}
+
+impl Drop for LaunchGuard<'_> {
+    fn drop(&mut self) {
+        self.state.launch_in_progress.store(false, Ordering::SeqCst);
+        if self.state.lock_pending.swap(false, Ordering::SeqCst) && lock_if_protected(self.state) {
+            let _ = self.window.emit("vault-locked", ());
+        }
+    }
 }
 
 #[tauri::command]
 async fn save_settings(
     mut settings: Settings,
     state: tauri::State<'_, AppState>,
-) -> Result<(), String> {
-    println!("Saving settings: {:?}", settings);
-
+) -> Result<(), NidaleeError> {
     settings.riot_client_path = settings.riot_client_path.replace('/', "\\");
     settings.league_path = settings.league_path.replace('/', "\\");
     settings.valorant_path = settings.valorant_path.replace('/', "\\");
 
-    if let Err(e) = set_auto_startup(settings.start_with_windows) {
-        println!("Failed to set auto startup: {}", e);
+    if let Err(e) = set_auto_startup(state.platform.registry.as_ref(), settings.start_with_windows) {
+        warn!("Failed to set auto startup: {}", e);
     }
 
+    apply_log_settings(&settings);
     let mut current_settings = state.settings.lock().unwrap();
     *current_settings = settings;
 
-    let settings_path = get_app_data_dir()?.join("settings.json");
-    let settings_json = serde_json::to_string_pretty(&*current_settings).map_err(|e| {
-        println!("Failed to serialize settings: {}", e);
-        e.to_string()
+    state.store.lock().unwrap().save_settings(&current_settings).map_err(|e| {
+        warn!("Failed to write settings: {}", e);
+        e
     })?;
 
-    fs::write(settings_path, settings_json).map_err(|e| {
-        println!("Failed to write settings file: {}", e);
-        e.to_string()
-    })?;
-
-    println!("Settings saved successfully");
+    info!("Settings saved successfully");
     Ok(())
 }
 
 #[tauri::command]
-async fn get_settings(state: tauri::State<'_, AppState>) -> Result<Settings, String> {
+async fn get_settings(state: tauri::State<'_, AppState>) -> Result<Settings, NidaleeError> {
     let settings = state.settings.lock().unwrap();
     Ok(settings.clone())
 }
 
-fn wait_for_riot_client() -> Result<(), String> {
-    println!("Waiting for Riot Client window...");
-    let max_attempts = 30;
-    let mut attempts = 0;
-
-    while attempts < max_attempts {
-        unsafe {
-            let window = FindWindowA(
-                PCSTR::from_raw("Chrome_WidgetWin_1\0".as_ptr()),
-                PCSTR::from_raw("Riot Client\0".as_ptr()),
-            );
-
-            let window2 = FindWindowA(
-                PCSTR::from_raw("RCLIENT\0".as_ptr()),
-                PCSTR::from_raw("Riot Client\0".as_ptr()),
-            );
-
-            if window != HWND(0) || window2 != HWND(0) {
-                thread::sleep(Duration::from_secs(2));
-                println!("Riot Client window found!");
-                return Ok(());
-            }
-        }
-
-        thread::sleep(Duration::from_secs(1));
-        attempts += 1;
-    }
-
-    Err("Riot Client window not found after 30 seconds".to_string())
-}
-
 #[tauri::command]
 async fn launch_game(
+    app: tauri::AppHandle,
     window: tauri::Window,
-    state: tauri::State<'_, AppState>,
-    account: Account,
+    account_id: String,
     selected_game: String,
-) -> Result<(), String> {
-    let (riot_client_path, login_delay, minimize_on_launch) = {
-        let settings = state.settings.lock().unwrap();
-        (
-            settings.riot_client_path.clone(),
-            settings.login_delay.clamp(2, 30) as u64,
-            settings.minimize_on_game_launch,
-        )
-    };
-
-    let game_status = check_game_status().await?;
-    if (selected_game == "league" && game_status.league_running) 
-        || (selected_game == "valorant" && game_status.valorant_running) {
-        return Err("Game is already running".to_string());
-    }
-
-    if riot_client_path.is_empty() || !verify_riot_client_path(&riot_client_path) {
-        let new_path = find_riot_client_path()
-            .ok_or("Could not find Riot Client. Please set the path in Settings.")?;
-        
-        {
-            let mut settings = state.settings.lock().unwrap();
-            settings.riot_client_path = new_path.clone();
-            let settings_path = get_app_data_dir()?.join("settings.json");
-            if let Ok(json) = serde_json::to_string(&*settings) {
-                fs::write(settings_path, json).map_err(|e| e.to_string())?;
-            }
-        }
-    }
+) -> Result<(), NidaleeError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        run_launch(&state, &window, &account_id, &selected_game)
+    })
+    .await
+    .map_err(|e| NidaleeError::Internal(e.to_string()))?
+}
 
-    if minimize_on_launch {
-        let _ = window.hide();
+#[tauri::command]
+async fn cancel_launch(state: tauri::State<'_, AppState>) -> Result<(), NidaleeError> {
+    if state.launch_in_progress.load(Ordering::SeqCst) {
+        info!("Cancelling launch");
+        state.launch_cancel.lock().unwrap().cancel();
     }
+    Ok(())
+}
 
-    println!("Launching Riot Client from: {}", riot_client_path);
-
-    #[cfg(target_os = "windows")]
-    let _command = Command::new(&riot_client_path)
-        .creation_flags(0x08000000)
-        .spawn()
-        .map_err(|e| {
-            println!("Failed to spawn Riot Client: {}", e);
-            e.to_string()
-        })?;
-
-    wait_for_riot_client()?;
-    
-    fn is_updating() -> bool {
-        unsafe {
-            let riot_update_window = FindWindowA(
-                PCSTR::from_raw("Chrome_WidgetWin_1\0".as_ptr()),
-                PCSTR::null(),
-            );
-            
-            let valorant_update_window = FindWindowA(
-                PCSTR::from_raw("VALORANT  \0".as_ptr()),
-                PCSTR::null(),
-            );
-
-            let league_update_window = FindWindowA(
-                PCSTR::from_raw("RiotClientMainWindow\0".as_ptr()),
-                PCSTR::null(),
-            );
-
-            let mut is_updating = false;
-            let mut text = [0u8; 256];
-
-            if riot_update_window != HWND(0) {
-                GetWindowTextA(riot_update_window, &mut text);
-                let window_text = String::from_utf8_lossy(&text).to_string();
-                is_updating |= window_text.contains("Update") || 
-                              window_text.contains("Installing") ||
-                              window_text.contains("Updating");
-            }
-
-            if valorant_update_window != HWND(0) {
-                GetWindowTextA(valorant_update_window, &mut text);
-                let window_text = String::from_utf8_lossy(&text).to_string();
-                is_updating |= window_text.contains("Update") || 
-                              window_text.contains("Installing") ||
-                              window_text.contains("Updating");
-            }
-
-            if league_update_window != HWND(0) {
-                GetWindowTextA(league_update_window, &mut text);
-                let window_text = String::from_utf8_lossy(&text).to_string();
-                is_updating |= window_text.contains("Update") || 
-                              window_text.contains("Installing") ||
-                              window_text.contains("Updating");
-            }
-
-            is_updating
-        }
+fn run_launch(
+    state: &AppState,
+    window: &tauri::Window,
+    account_id: &str,
+    selected_game: &str,
+) -> Result<(), NidaleeError> {
+    let settings = state.settings.lock().unwrap().clone();
+    let mut riot_client_path = settings.riot_client_path.clone();
+
+    state.idle.touch();
+    let launch_guard = LaunchGuard::new(state, window.clone())?;
+    let cancel = launch_guard.cancel.clone();
+    let account = state.vault.account(account_id)?;
+
+    let game = state
+        .games
+        .get(selected_game)
+        .cloned()
+        .ok_or_else(|| NidaleeError::UnknownGame(selected_game.to_string()))?;
+    let patchline = game.patchline(account.patchline.as_deref())?.to_string();
+    check_patchline_installed(&game, &patchline)?;
+
+    let platform = &state.platform;
+    let status = games::game_status(&state.games, platform.processes.as_ref())?;
+    if games::is_game_running(&status, &game.id) {
+        return Err(NidaleeError::GameAlreadyRunning);
     }
 
-    let mut update_check_attempts = 0;
-    while update_check_attempts < 180 {
-        if is_updating() {
-            println!("Game update in progress, waiting... (attempt {}/180)", update_check_attempts + 1);
-            thread::sleep(Duration::from_secs(1));
-            update_check_attempts += 1;
-        } else {
-            thread::sleep(Duration::from_millis(500));
-            if !is_updating() {
-                break;
-            }
-        }
-    }
+    if riot_client_path.is_empty() || !verify_riot_client_path(&riot_client_path) {
+        riot_client_path = find_riot_client_path(platform, &cancel, &mut |_| {})
+            .ok_or(NidaleeError::ClientNotFound)?;
 
-    if update_check_attempts >= 180 {
-        return Err("Game update is taking too long. Please try again later.".to_string());
+        let mut current = state.settings.lock().unwrap();
+        current.riot_client_path = riot_client_path.clone();
+        state.store.lock().unwrap().save_settings(&current)?;
     }
 
-    println!("No updates detected or updates completed. Proceeding with login...");
-
-    println!("Waiting {} seconds for client to load...", login_delay);
-    thread::sleep(Duration::from_secs(login_delay));
-
-    println!("Starting login sequence");
-    let mut enigo = Enigo::new();
-
-    thread::sleep(Duration::from_millis(500));
-
-    unsafe {
-        let window = FindWindowA(
-            PCSTR::from_raw("Chrome_WidgetWin_1\0".as_ptr()),
-            PCSTR::from_raw("Riot Client\0".as_ptr()),
-        );
-
-        let window2 = FindWindowA(
-            PCSTR::from_raw("RCLIENT\0".as_ptr()),
-            PCSTR::from_raw("Riot Client\0".as_ptr()),
-        );
-
-        let target_window = if window != HWND(0) { window } else { window2 };
-
-        if target_window != HWND(0) {
-            let mut rect = RECT::default();
-            GetWindowRect(target_window, &mut rect);
-            
-            let window_width = rect.right - rect.left;
-            let window_height = rect.bottom - rect.top;
-            let target_x = rect.left + (window_width as f32 * 0.15) as i32;
-            let target_y = rect.top + (window_height as f32 * 0.30) as i32;
-
-            BringWindowToTop(target_window);
-            SetForegroundWindow(target_window);
-
-            thread::sleep(Duration::from_millis(500));
-
-            enigo.mouse_move_to(target_x, target_y);
-            thread::sleep(Duration::from_millis(50));
-            enigo.mouse_click(MouseButton::Left);
-            thread::sleep(Duration::from_millis(200));
-
-            for c in account.username.chars() {
-                enigo.key_sequence(&c.to_string());
-                thread::sleep(Duration::from_millis(5));
-            }
-            thread::sleep(Duration::from_millis(100));
-
-            enigo.key_click(Key::Tab);
-            thread::sleep(Duration::from_millis(100));
-
-            for c in account.password.chars() {
-                enigo.key_sequence(&c.to_string());
-                thread::sleep(Duration::from_millis(5));
-            }
-            thread::sleep(Duration::from_millis(100));
-
-            enigo.key_click(Key::Return);
-            thread::sleep(Duration::from_secs(1));
-        }
+    if settings.minimize_on_game_launch {
+        let _ = window.hide();
     }
 
-    println!("Login complete, waiting for client to be ready...");
-    thread::sleep(Duration::from_millis(1500));
-
-    println!("Launching game: {}", selected_game);
-    let launch_args = match selected_game.as_str() {
-        "valorant" => "--launch-product=valorant --launch-patchline=live",
-        "league" => "--launch-product=league_of_legends --launch-patchline=live",
-        _ => return Err("Invalid game type".to_string()),
+    let request = LaunchRequest {
+        game: &game,
+        patchline: &patchline,
+        riot_client_path: &riot_client_path,
+        username: &account.username,
+        password: &account.password,
+        login_delay: Duration::from_secs(settings.login_delay.clamp(2, 30) as u64),
+        entry_mode: settings.credential_entry,
+        field_location: &settings.field_location,
     };
-
-    let max_launch_attempts = 5;
-    let mut current_attempt = 0;
-
-    while current_attempt < max_launch_attempts {
-        current_attempt += 1;
-        println!("Attempting to launch game (attempt {}/{})", current_attempt, max_launch_attempts);
-
-        #[cfg(target_os = "windows")]
-        Command::new(&riot_client_path)
-            .creation_flags(0x08000000)
-            .args(launch_args.split_whitespace())
-            .spawn()
-            .map_err(|e| {
-                println!("Failed to launch game: {}", e);
-                e.to_string()
-            })?;
-
-        let mut check_attempts = 0;
-        let max_checks = 4;
-
-        while check_attempts < max_checks {
-            thread::sleep(Duration::from_secs(2));
-            check_attempts += 1;
-            
-            let current_status = check_game_status().await?;
-            let game_running = match selected_game.as_str() {
-                "valorant" => current_status.valorant_running,
-                "league" => current_status.league_running,
-                _ => false,
-            };
-
-            if game_running {
-                println!("Game launched successfully on attempt {}", current_attempt);
-                
-                let mut accounts = state.accounts.lock().unwrap();
-                if let Some(acc) = accounts.get_mut(&account.id) {
-                    acc.last_login = Some(Utc::now().to_rfc3339());
-                    let accounts_path = get_app_data_dir()?.join("accounts.json");
-                    let accounts_json = serde_json::to_string(&*accounts).map_err(|e| e.to_string())?;
-                    fs::write(accounts_path, accounts_json).map_err(|e| e.to_string())?;
-                }
-                
-                return Ok(());
-            }
-
-            println!("Game not detected yet, checking again in 2 seconds... (check {}/{})", check_attempts, max_checks);
-        }
-
-        println!("Game launch attempt {} failed, retrying...", current_attempt);
-        
-        if current_attempt < max_launch_attempts {
-            let _ = force_close_game(selected_game.clone()).await;
-            thread::sleep(Duration::from_secs(1));
+    let launcher = Launcher::new(
+        platform,
+        state.riot_api.as_ref(),
+        &SystemClock,
+        &settings.launch,
+        cancel,
+    );
+    let result = launcher.run(&request, &mut |progress| {
+        let _ = window.emit("launch-progress", &progress);
+    });
+
+    if let Err(e) = result {
+        if e != NidaleeError::LaunchCancelled {
+            let _ = state
+                .store
+                .lock()
+                .unwrap()
+                .record_launch(&account.id, &game.id, false);
         }
+        return Err(e);
     }
 
-    Err(format!("Failed to launch {} after {} attempts. Please try again or launch the game manually.", selected_game, max_launch_attempts))
+    state.vault.record_launch(&state.store, &account.id, &game.id)?;
+    Ok(())
 }
 
 fn get_windows_drives() -> Vec<String> {
@@ -435,27 +381,40 @@ fn get_windows_drives() -> Vec<String> {
     drives
 }
 
-fn find_riot_client_path() -> Option<String> {
-    println!("Starting Riot Client path search...");
+// Cheap, exact sources first. The drive walk at the end is bounded by SearchLimits
+// and stops as soon as `cancel` is set.
+fn find_riot_client_path(
+    platform: &Platform,
+    cancel: &CancelToken,
+    progress: &mut dyn FnMut(&SearchProgress),
+) -> Option<String> {
+    debug!("Starting Riot Client path search...");
+
+    debug!("Checking {}...", installs::CLIENT_INSTALLS_FILE);
+    let manifest_path = installs::riot_games_dir()
+        .and_then(|dir| installs::read_client_installs(&dir).ok())
+        .and_then(|manifest| manifest.client_paths().into_iter().next());
+    if let Some(path) = manifest_path {
+        info!("Found via installs manifest: {}", path.display());
+        return Some(path.to_string_lossy().into_owned());
+    }
 
     let registry_paths = [
         (
-            HKEY_LOCAL_MACHINE,
+            Hive::LocalMachine,
             "SOFTWARE\\WOW6432Node\\Riot Games\\Riot Client",
         ),
-        (HKEY_LOCAL_MACHINE, "SOFTWARE\\Riot Games\\Riot Client"),
-        (HKEY_CURRENT_USER, "SOFTWARE\\Riot Games\\Riot Client"),
+        (Hive::LocalMachine, "SOFTWARE\\Riot Games\\Riot Client"),
+        (Hive::CurrentUser, "SOFTWARE\\Riot Games\\Riot Client"),
     ];
 
-    println!("Checking registry keys...");
-    for (hkey, path) in &registry_paths {
-        if let Ok(key) = RegKey::predef(*hkey).open_subkey(path) {
-            if let Ok(install_dir) = key.get_value::<String, _>("InstallLocation") {
-                let client_path = Path::new(&install_dir).join("RiotClientServices.exe");
-                if client_path.exists() {
-                    println!("Found via registry: {}", client_path.display());
-                    return Some(client_path.to_string_lossy().into_owned());
-                }
+    debug!("Checking registry keys...");
+    for (hive, path) in registry_paths {
+        if let Some(install_dir) = platform.registry.read_string(hive, path, "InstallLocation") {
+            let client_path = Path::new(&install_dir).join(CLIENT_EXECUTABLE);
+            if client_path.exists() {
+                info!("Found via registry: {}", client_path.display());
+                return Some(client_path.to_string_lossy().into_owned());
             }
         }
     }
@@ -466,93 +425,51 @@ fn find_riot_client_path() -> Option<String> {
         "C:\\Program Files (x86)\\Riot Games\\Riot Client\\RiotClientServices.exe",
     ];
 
-    println!("Checking common paths...");
+    debug!("Checking common paths...");
     for path in &common_paths {
         if Path::new(path).exists() {
-            println!("Found in common path: {}", path);
+            info!("Found in common path: {}", path);
             return Some(path.to_string());
         }
     }
 
-    println!("Searching all drives...");
-    for drive in get_windows_drives() {
-        let direct_path = format!("{}Riot Games\\Riot Client\\RiotClientServices.exe", drive);
-        if Path::new(&direct_path).exists() {
-            println!("Found in drive root: {}", direct_path);
-            return Some(direct_path);
-        }
-
-        let program_files = vec![
-            format!("{}Program Files\\Riot Games", drive),
-            format!("{}Program Files (x86)\\Riot Games", drive),
-            format!("{}Riot Games", drive),
-        ];
-
-        for dir in program_files {
-            if let Some(path) = search_directory_for_riot_client(&dir) {
-                println!("Found via directory search: {}", path);
-                return Some(path);
-            }
-        }
-    }
-
-    println!("Checking running processes...");
-    if let Some(path) = find_riot_client_from_process() {
-        println!("Found via process: {}", path);
+    debug!("Checking running processes...");
+    let running_client = platform.processes.list().ok().and_then(|running| {
+        running
+            .into_iter()
+            .find(|process| process.is_named(CLIENT_EXECUTABLE))
+            .and_then(|process| process.path)
+    });
+    if let Some(path) = running_client {
+        info!("Found via process: {}", path);
         return Some(path);
     }
 
-    println!("Riot Client not found in any location");
-    None
-}
-
-fn search_directory_for_riot_client(start_path: &str) -> Option<String> {
-    if !Path::new(start_path).exists() {
-        return None;
-    }
-
-    let target = "RiotClientServices.exe";
-    let mut dirs_to_check = vec![start_path.to_string()];
-
-    while let Some(dir) = dirs_to_check.pop() {
-        if let Ok(entries) = read_dir(&dir) {
-            for entry in entries.filter_map(Result::ok) {
-                let path = entry.path();
-                if path.is_file() && path.file_name().and_then(|n| n.to_str()) == Some(target) {
-                    return Some(path.to_string_lossy().into_owned());
-                } else if path.is_dir() {
-                    dirs_to_check.push(path.to_string_lossy().into_owned());
-                }
-            }
+    debug!("Searching all drives...");
+    let roots: Vec<PathBuf> = get_windows_drives()
+        .iter()
+        .flat_map(|drive| {
+            [
+                format!("{}Riot Games", drive),
+                format!("{}Program Files\\Riot Games", drive),
+                format!("{}Program Files (x86)\\Riot Games", drive),
+            ]
+        })
+        .map(PathBuf::from)
+        .collect();
+    let limits = SearchLimits::default();
+    let outcome =
+        client_search::search(&roots, CLIENT_EXECUTABLE, &limits, &SystemClock, cancel, progress);
+    match outcome {
+        SearchOutcome::Found(path) => {
+            info!("Found via directory search: {}", path.display());
+            Some(path.to_string_lossy().into_owned())
         }
-    }
-    None
-}
-
-fn find_riot_client_from_process() -> Option<String> {
-    #[cfg(windows)]
-    {
-        let output = Command::new("wmic")
-            .creation_flags(0x08000000)
-            .args([
-                "process",
-                "where",
-                "name='RiotClientServices.exe'",
-                "get",
-                "ExecutablePath",
-            ])
-            .output()
-            .ok()?;
-
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        for line in output_str.lines() {
-            let trimmed = line.trim();
-            if trimmed.ends_with("RiotClientServices.exe") {
-                return Some(trimmed.to_string());
-            }
+        outcome => {
+            warn!("Riot Client not found in any location ({:?})", outcome);
+            None
         }
     }
-    None
 }
 
 fn verify_riot_client_path(path: &str) -> bool {
@@ -560,53 +477,48 @@ fn verify_riot_client_path(path: &str) -> bool {
     Path::new(&normalized_path).exists()
 }
 
-fn set_auto_startup(enable: bool) -> Result<(), String> {
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    let path = r"Software\Microsoft\Windows\CurrentVersion\Run";
-    let (key, _) = hkcu.create_subkey(path).map_err(|e| e.to_string())?;
+const STARTUP_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
 
+fn set_auto_startup(registry: &dyn Registry, enable: bool) -> Result<(), String> {
     if enable {
         let clean_path = get_clean_exe_path()?;
         let registry_value = format!("\"{}\" --start-minimized", clean_path);
-        key.set_value("Nidalee", &registry_value)
-            .map_err(|e| e.to_string())?;
+        registry.write_string(Hive::CurrentUser, STARTUP_KEY, "Nidalee", &registry_value)?;
     } else {
-        let _ = key.delete_value("Nidalee");
+        let _ = registry.delete_value(Hive::CurrentUser, STARTUP_KEY, "Nidalee");
     }
     Ok(())
 }
 
-fn verify_startup_path() -> Result<(), String> {
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    let path = r"Software\Microsoft\Windows\CurrentVersion\Run";
-
-    if let Ok((key, _)) = hkcu.create_subkey(path) {
-        if let Ok(current_path) = key.get_value::<String, _>("Nidalee") {
-            let clean_current = current_path.replace(r"\\?\", "");
-            let clean_path = get_clean_exe_path()?;
-            let expected_value = format!("\"{}\" --start-minimized", clean_path);
+fn verify_startup_path(registry: &dyn Registry) -> Result<(), String> {
+    if let Some(current_path) = registry.read_string(Hive::CurrentUser, STARTUP_KEY, "Nidalee") {
+        let clean_current = current_path.replace(r"\\?\", "");
+        let clean_path = get_clean_exe_path()?;
+        let expected_value = format!("\"{}\" --start-minimized", clean_path);
 
-            if clean_current != expected_value {
-                key.set_value("Nidalee", &expected_value)
-                    .map_err(|e| e.to_string())?;
-            }
+        if clean_current != expected_value {
+            registry.write_string(Hive::CurrentUser, STARTUP_KEY, "Nidalee", &expected_value)?;
         }
     }
     Ok(())
 }
 
 #[tauri::command]
-async fn toggle_auto_start(enable: bool) -> Result<(), String> {
-    println!("toggle_auto_start called with enable={}", enable);
-    set_auto_startup(enable)
+async fn toggle_auto_start(
+    enable: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), NidaleeError> {
+    debug!("toggle_auto_start called with enable={}", enable);
+    Ok(set_auto_startup(state.platform.registry.as_ref(), enable)?)
 }
 
 #[tauri::command]
-async fn get_auto_start_status() -> Result<bool, String> {
-    Ok(RegKey::predef(HKEY_CURRENT_USER)
-        .open_subkey(r"Software\Microsoft\Windows\CurrentVersion\Run")
-        .and_then(|key| key.get_value::<String, _>("Nidalee"))
-        .is_ok())
+async fn get_auto_start_status(state: tauri::State<'_, AppState>) -> Result<bool, NidaleeError> {
+    Ok(state
+        .platform
+        .registry
+        .read_string(Hive::CurrentUser, STARTUP_KEY, "Nidalee")
+        .is_some())
 }
 
 fn get_app_data_dir() -> Result<PathBuf, String> {
@@ -622,33 +534,23 @@ fn get_app_data_dir() -> Result<PathBuf, String> {
 #[tauri::command]
 async fn save_categories(
     categories: Vec<String>,
-    _state: tauri::State<'_, AppState>,
-) -> Result<(), String> {
-    let categories_path = get_app_data_dir()?.join("categories.json");
-    let categories_json =
-        serde_json::to_string_pretty(&Categories { categories }).map_err(|e| e.to_string())?;
-    fs::write(categories_path, categories_json).map_err(|e| e.to_string())?;
-    Ok(())
+    state: tauri::State<'_, AppState>,
+) -> Result<(), NidaleeError> {
+    state.store.lock().unwrap().save_categories(&categories)
 }
 
 #[tauri::command]
-async fn get_categories() -> Result<Vec<String>, String> {
-    let categories_path = get_app_data_dir()?.join("categories.json");
-    if let Ok(content) = fs::read_to_string(categories_path) {
-        let categories: Categories = serde_json::from_str(&content).map_err(|e| e.to_string())?;
-        Ok(categories.categories)
-    } else {
-        Ok(Vec::new())
-    }
+async fn get_categories(state: tauri::State<'_, AppState>) -> Result<Vec<String>, NidaleeError> {
+    state.store.lock().unwrap().load_categories()
 }
 
 #[tauri::command]
-async fn check_first_run() -> Result<bool, String> {
+async fn check_first_run() -> Result<bool, NidaleeError> {
     let app_data_dir = get_app_data_dir()?;
     let install_marker = app_data_dir.join(".installed");
 
     if !install_marker.exists() {
-        fs::write(&install_marker, "").map_err(|e| e.to_string())?;
+        fs::write(&install_marker, "")?;
         Ok(true)
     } else {
         Ok(false)
@@ -670,180 +572,249 @@ fn get_clean_exe_path() -> Result<String, String> {
 async fn minimize_window(
     window: tauri::Window,
     _state: tauri::State<'_, AppState>,
-) -> Result<(), String> {
-    let settings = _state.settings.lock().unwrap();
-    if settings.minimize_to_tray {
+) -> Result<(), NidaleeError> {
+    let minimize_to_tray = _state.settings.lock().unwrap().minimize_to_tray;
+    if minimize_to_tray {
         window.hide().map_err(|e| e.to_string())?;
     } else {
         window.minimize().map_err(|e| e.to_string())?;
     }
+
+    if lock_if_protected(&_state) {
+        let _ = window.emit("vault-locked", ());
+    }
     Ok(())
 }
 
-#[tauri::command]
-async fn check_game_status() -> Result<GameStatus, String> {
-    let output = Command::new("tasklist")
-        .creation_flags(0x08000000)
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    let process_list = String::from_utf8_lossy(&output.stdout);
-
-    Ok(GameStatus {
-        league_running: process_list.contains("League of Legends.exe")
-            || process_list.contains("LeagueClient.exe")
-            || process_list.contains("LeagueClientUx.exe"),
-        valorant_running: process_list.contains("VALORANT.exe")
-            || process_list.contains("VALORANT-Win64-Shipping.exe"),
-    })
-}
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
 
-#[tauri::command]
-async fn force_close_game(game_type: String) -> Result<(), String> {
-    let processes = match game_type.as_str() {
-        "league" => vec![
-            "League of Legends.exe",
-            "LeagueClient.exe",
-            "LeagueClientUx.exe",
-            "LeagueClientUxRender.exe",
-            "RiotClientServices.exe",
-            "RiotClientUx.exe",
-            "RiotClientUxRender.exe",
-        ],
-        "valorant" => vec![
-            "VALORANT.exe",
-            "VALORANT-Win64-Shipping.exe",
-            "RiotClientServices.exe",
-            "RiotClientUx.exe",
-            "RiotClientUxRender.exe",
-        ],
-        _ => return Err("Invalid game type".to_string()),
+fn check_patchline_installed(game: &GameDefinition, patchline: &str) -> Result<(), NidaleeError> {
+    let Some(metadata_dir) = installs::metadata_dir() else {
+        warn!("Riot Client metadata not found, skipping patchline check");
+        return Ok(());
     };
-
-    println!("Attempting to close game processes...");
-
-    for process in &processes {
-        println!("Closing process: {}", process);
-        let _ = Command::new("taskkill")
-            .creation_flags(0x08000000)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .args(["/IM", process])
-            .output();
-        thread::sleep(Duration::from_millis(100));
+    if installs::is_patchline_installed(&metadata_dir, &game.product_id, patchline) {
+        Ok(())
+    } else {
+        Err(NidaleeError::GameNotInstalled {
+            game: game.display_name.clone(),
+            patchline: patchline.to_string(),
+        })
     }
+}
 
-    for process in &processes {
-        println!("Force closing process: {}", process);
-        let _ = Command::new("taskkill")
-            .creation_flags(0x08000000)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .args(["/F", "/IM", process])
-            .output();
-        thread::sleep(Duration::from_millis(50));
+fn discover_installs(games: &GameRegistry) -> Vec<GameInstall> {
+    match installs::metadata_dir() {
+        Some(metadata_dir) => installs::discover(&metadata_dir, games),
+        None => Vec::new(),
     }
+}
 
-    println!("Waiting for processes to terminate...");
-    thread::sleep(Duration::from_secs(1));
+// Fills in league_path and valorant_path from the discovered installs, replacing
+// saved paths that no longer hold the game. Returns whether anything changed.
+fn apply_install_paths(
+    settings: &mut Settings,
+    games: &GameRegistry,
+    found: &[GameInstall],
+) -> bool {
+    let mut changed = false;
+    for (game_id, saved) in [
+        ("league", &mut settings.league_path),
+        ("valorant", &mut settings.valorant_path),
+    ] {
+        let Some(game) = games.get(game_id) else {
+            continue;
+        };
+        if !saved.is_empty()
+            && installs::validate_install(Path::new(saved.as_str()), &game.processes).is_ok()
+        {
+            continue;
+        }
+        let default_patchline = game.patchline(None).ok();
+        let install = found
+            .iter()
+            .filter(|install| install.game_id == game_id)
+            .find(|install| Some(install.patchline.as_str()) == default_patchline)
+            .or_else(|| found.iter().find(|install| install.game_id == game_id));
+        match install {
+            Some(install) if *saved != install.path => {
+                info!("Found {} install: {}", game.display_name, install.path);
+                *saved = install.path.clone();
+                changed = true;
+            }
+            Some(_) => {}
+            None if !saved.is_empty() => {
+                warn!("Saved {} path is not valid: {}", game.display_name, saved)
+            }
+            None => {}
+        }
+    }
+    changed
+}
 
-    let output = Command::new("tasklist")
-        .creation_flags(0x08000000)
-        .output()
-        .map_err(|e| e.to_string())?;
+#[derive(Debug, Serialize, Clone)]
+struct InstallScan {
+    riot_client_path: Option<String>,
+    installs: Vec<GameInstall>,
+}
 
-    let process_list = String::from_utf8_lossy(&output.stdout);
+// One scan runs at a time; starting another cancels the one in progress. A saved
+// client path that still exists is kept unless `search_client` asks for a fresh search.
+fn scan_installs(
+    state: &AppState,
+    search_client: bool,
+    progress: &mut dyn FnMut(&SearchProgress),
+) -> Result<InstallScan, NidaleeError> {
+    let cancel = CancelToken::default();
+    {
+        let mut current = state.install_scan.lock().unwrap();
+        current.cancel();
+        *current = cancel.clone();
+    }
 
-    for process in &processes {
-        if process_list.contains(process) {
-            return Err(format!("Failed to close process: {}", process));
+    let saved = state.settings.lock().unwrap().riot_client_path.clone();
+    let riot_client_path = if search_client || !verify_riot_client_path(&saved) {
+        find_riot_client_path(&state.platform, &cancel, progress)
+    } else {
+        Some(saved)
+    };
+    if cancel.is_cancelled() {
+        return Err(NidaleeError::ScanCancelled);
+    }
+    let installs = discover_installs(&state.games);
+
+    let mut settings = state.settings.lock().unwrap();
+    let mut changed = apply_install_paths(&mut settings, &state.games, &installs);
+    if let Some(path) = &riot_client_path {
+        if settings.riot_client_path != *path {
+            settings.riot_client_path = path.clone();
+            changed = true;
         }
     }
+    if changed {
+        state.store.lock().unwrap().save_settings(&settings)?;
+    }
+    Ok(InstallScan {
+        riot_client_path,
+        installs,
+    })
+}
 
-    println!("All game processes closed successfully");
-    Ok(())
+#[tauri::command]
+async fn rescan_installs(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+) -> Result<InstallScan, NidaleeError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        scan_installs(&state, true, &mut |progress| {
+            let _ = window.emit("install-scan-progress", progress);
+        })
+    })
+    .await
+    .map_err(|e| NidaleeError::Internal(e.to_string()))?
 }
 
-fn main() {
-    let mutex_name = "Global\\NidaleeAppSingleInstance";
-    let mutex_name_wide: Vec<u16> = String::from(mutex_name)
-        .encode_utf16()
-        .chain(std::iter::once(0))
-        .collect();
+#[tauri::command]
+async fn get_installs(state: tauri::State<'_, AppState>) -> Result<Vec<GameInstall>, NidaleeError> {
+    Ok(discover_installs(&state.games))
+}
 
-    unsafe {
-        let existing_mutex =
-            OpenMutexW(MUTEX_ALL_ACCESS, false, PCWSTR(mutex_name_wide.as_ptr())).ok();
+#[tauri::command]
+async fn check_game_status(state: tauri::State<'_, AppState>) -> Result<GameStatus, NidaleeError> {
+    Ok(games::game_status(
+        &state.games,
+        state.platform.processes.as_ref(),
+    )?)
+}
 
-        if let Some(_mutex) = existing_mutex {
-            let window = FindWindowA(PCSTR::null(), PCSTR::from_raw("Nidalee\0".as_ptr()));
+#[tauri::command]
+async fn get_games(state: tauri::State<'_, AppState>) -> Result<Vec<GameDefinition>, NidaleeError> {
+    Ok(state.games.all().to_vec())
+}
 
-            if window != HWND(0) {
-                use windows::Win32::UI::WindowsAndMessaging::{
-                    IsIconic, ShowWindow, SW_HIDE, SW_MINIMIZE, SW_RESTORE, SW_SHOW,
-                };
+#[tauri::command]
+async fn get_client_session(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<SignedInAccount>, NidaleeError> {
+    Ok(state.riot_api.session()?)
+}
+
+#[tauri::command]
+async fn force_close_game(
+    app: tauri::AppHandle,
+    game_type: String,
+) -> Result<CloseReport, NidaleeError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        shutdown::close_game(&state.platform, &state.games, &game_type, &CloseOptions::default())
+    })
+    .await
+    .map_err(|e| NidaleeError::Internal(e.to_string()))?
+}
+
+fn main() {
+    let platform = Platform::native();
 
-                if IsIconic(window).as_bool() {
-                    ShowWindow(window, SW_RESTORE);
-                    ShowWindow(window, SW_SHOW);
-                    BringWindowToTop(window);
-                    SetForegroundWindow(window);
+    if !platform::acquire_single_instance("Global\\NidaleeAppSingleInstance") {
+        if let Some(window) = platform.windows.find(None, Some("Nidalee")) {
+            if platform.windows.is_minimized(window) {
+                platform.windows.restore(window);
+            } else {
+                let db_path = get_app_data_dir().unwrap().join(db::DATABASE_FILE);
+                let minimize_to_tray = db::read_settings(&db_path)
+                    .map(|settings| settings.minimize_to_tray)
+                    .unwrap_or(false);
+                if minimize_to_tray {
+                    platform.windows.hide(window);
                 } else {
-                    let settings_path = get_app_data_dir().unwrap().join("settings.json");
-                    if let Ok(content) = fs::read_to_string(&settings_path) {
-                        if let Ok(settings) = serde_json::from_str::<Settings>(&content) {
-                            if settings.minimize_to_tray {
-                                ShowWindow(window, SW_HIDE);
-                            } else {
-                                ShowWindow(window, SW_MINIMIZE);
-                            }
-                        } else {
-                            ShowWindow(window, SW_MINIMIZE);
-                        }
-                    } else {
-                        ShowWindow(window, SW_MINIMIZE);
-                    }
+                    platform.windows.minimize(window);
                 }
-                return;
             }
-            return;
         }
-
-        let _mutex = CreateMutexW(Option::None, true, PCWSTR(mutex_name_wide.as_ptr()));
+        return;
     }
 
     let app_data_dir = get_app_data_dir().unwrap();
-    println!("Using app data directory: {}", app_data_dir.display());
+    match logging::init(&app_data_dir) {
+        Ok(log_path) => info!("Logging to {}", log_path.display()),
+        Err(e) => eprintln!("Failed to start logging: {}", e),
+    }
+    info!("Using app data directory: {}", app_data_dir.display());
 
-    let _settings_path = app_data_dir.join("settings.json");
-    let _accounts_path = app_data_dir.join("accounts.json");
-    let _categories_path = app_data_dir.join("categories.json");
+    let mut store: Box<dyn Store> = match db::open(&app_data_dir.join(db::DATABASE_FILE)) {
+        Ok(conn) => {
+            if let Err(e) = db::import_legacy_json(&conn, &app_data_dir) {
+                warn!("Failed to import legacy JSON files: {}", e);
+            }
+            Box::new(SqliteStore::new(conn))
+        }
+        // Running without the database would silently lose every change.
+        Err(e) => {
+            error!("Failed to open database: {}", e);
+            MessageDialogBuilder::new(
+                "Nidalee",
+                format!("Nidalee could not open its database and will now close.\n\n{}", e),
+            )
+            .kind(MessageDialogKind::Error)
+            .show();
+            std::process::exit(1);
+        }
+    };
 
-    let riot_client_path = find_riot_client_path().unwrap_or_else(|| String::new());
-    println!("Found Riot Client path: {}", riot_client_path);
+    let games = GameRegistry::load(&app_data_dir.join(games::USER_GAMES_FILE));
 
-    let settings = if let Ok(content) = fs::read_to_string(&_settings_path) {
-        let mut settings: Settings = serde_json::from_str(&content).unwrap_or_else(|_| Settings {
-            riot_client_path: riot_client_path.clone(),
-            league_path: String::new(),
-            valorant_path: String::new(),
-            start_with_windows: false,
-            minimize_to_tray: false,
-            minimize_on_game_launch: false,
-            login_delay: 5,
-            preferred_monitor: None,
-        });
+    let stored_settings = store.load_settings().unwrap_or_else(|e| {
+        warn!("Failed to load settings: {}", e);
+        None
+    });
 
-        if settings.riot_client_path.is_empty() && !riot_client_path.is_empty() {
-            settings.riot_client_path = riot_client_path;
-            if let Ok(json) = serde_json::to_string_pretty(&settings) {
-                let _ = fs::write(&_settings_path, json);
-            }
-        }
+    // Install paths are filled in by the background scan started in setup.
+    let settings = if let Some(settings) = stored_settings {
         settings
     } else {
         let settings = Settings {
-            riot_client_path,
+            riot_client_path: String::new(),
             league_path: String::new(),
             valorant_path: String::new(),
             start_with_windows: false,
@@ -851,30 +822,72 @@ fn main() {
             minimize_on_game_launch: false,
             login_delay: 5,
             preferred_monitor: None,
+            auto_lock_minutes: default_auto_lock_minutes(),
+            launch: LaunchPolicy::default(),
+            credential_entry: EntryMode::default(),
+            field_location: FieldLocation::default(),
+            log_level: default_log_level(),
+            log_usernames: false,
         };
-        if let Ok(json) = serde_json::to_string_pretty(&settings) {
-            let _ = fs::write(&_settings_path, json);
-        }
+        let _ = store.save_settings(&settings);
         settings
     };
+    apply_log_settings(&settings);
 
-    let accounts = if let Ok(content) = fs::read_to_string(&_accounts_path) {
-        serde_json::from_str(&content).unwrap_or_else(|_| HashMap::new())
+    let password_protected = store.is_password_protected().unwrap_or_else(|e| {
+        warn!("Failed to read vault: {}", e);
+        false
+    });
+
+    let mut vault_error = None;
+    let (vault_key, accounts) = if password_protected {
+        info!("Vault is protected by a master password, starting locked");
+        (None, HashMap::new())
     } else {
-        HashMap::new()
+        // A key that doesn't match the stored vault would seal new accounts that can
+        // never be read back, so the vault stays locked instead.
+        let opened = vault::load_or_create_key(&app_data_dir.join("vault.key")).and_then(|key| {
+            store.attach_key(&key)?;
+            Ok(key)
+        });
+        match opened {
+            Ok(vault_key) => {
+                let accounts = store.load_accounts(&vault_key).unwrap_or_else(|e| {
+                    warn!("Failed to load accounts: {}", e);
+                    HashMap::new()
+                });
+                (Some(vault_key), accounts)
+            }
+            Err(e) => {
+                error!("Failed to open vault, starting locked: {}", e);
+                vault_error = Some(e.to_string());
+                (None, HashMap::new())
+            }
+        }
     };
 
     let app_state = AppState {
-        accounts: Mutex::new(accounts),
+        store: Mutex::new(store),
+        vault: AccountVault::new(vault_key, accounts, vault_error),
+        idle: IdleTimer::new(SystemClock),
+        launch_in_progress: AtomicBool::new(false),
+        launch_cancel: Mutex::new(CancelToken::default()),
+        install_scan: Mutex::new(CancelToken::default()),
+        lock_pending: AtomicBool::new(false),
         settings: Mutex::new(settings),
         last_move_time: AtomicU64::new(0),
         last_monitor: Mutex::new(None),
+        platform,
+        riot_api: Box::new(LocalApi::new(riot_api::lockfile_path())),
+        games,
     };
 
     let quit = CustomMenuItem::new("quit".to_string(), "Quit");
     let show = CustomMenuItem::new("show".to_string(), "Show");
+    let hide = CustomMenuItem::new("hide".to_string(), "Hide");
     let tray_menu = SystemTrayMenu::new()
         .add_item(show)
+        .add_item(hide)
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(quit);
     let system_tray = SystemTray::new()
@@ -894,6 +907,14 @@ fn main() {
                         window.set_focus().unwrap();
                     }
                 }
+                "hide" => {
+                    if let Some(window) = app.get_window("main") {
+                        window.hide().unwrap();
+                        if lock_if_protected(&app.state::<AppState>()) {
+                            let _ = window.emit("vault-locked", ());
+                        }
+                    }
+                }
                 _ => {}
             },
             SystemTrayEvent::LeftClick { .. } => {
@@ -912,6 +933,10 @@ fn main() {
             save_settings,
             get_settings,
             launch_game,
+            cancel_launch,
+            get_client_session,
+            get_installs,
+            rescan_installs,
             toggle_auto_start,
             get_auto_start_status,
             save_categories,
@@ -919,15 +944,68 @@ fn main() {
             check_first_run,
             minimize_window,
             check_game_status,
-            force_close_game
+            get_games,
+            force_close_game,
+            get_vault_status,
+            unlock_vault,
+            lock_vault,
+            change_master_password
         ])
         .setup(|app| {
-            if let Err(e) = verify_startup_path() {
-                println!("Failed to verify startup path: {}", e);
+            let state = app.state::<AppState>();
+            if let Err(e) = verify_startup_path(state.platform.registry.as_ref()) {
+                warn!("Failed to verify startup path: {}", e);
             }
 
+            let app_handle = app.handle();
+            thread::spawn(move || loop {
+                thread::sleep(Duration::from_secs(5));
+                let state = app_handle.state::<AppState>();
+                let auto_lock_minutes = state.settings.lock().unwrap().auto_lock_minutes;
+                let timeout = Duration::from_secs(auto_lock_minutes as u64 * 60);
+                if state.idle.is_expired(timeout) && lock_if_protected(&state) {
+                    info!("Vault auto-locked after {} minutes of inactivity", auto_lock_minutes);
+                    let _ = app_handle.emit_all("vault-locked", ());
+                }
+            });
+
+            let scan_handle = app.handle();
+            thread::spawn(move || {
+                let state = scan_handle.state::<AppState>();
+                let result = scan_installs(&state, false, &mut |progress| {
+                    let _ = scan_handle.emit_all("install-scan-progress", progress);
+                });
+                match result {
+                    Ok(scan) => {
+                        let _ = scan_handle.emit_all("install-scan-finished", &scan);
+                    }
+                    Err(e) => warn!("Install scan stopped: {}", e),
+                }
+            });
+
+            let watcher_handle = app.handle();
+            thread::spawn(move || {
+                let targets = watcher::targets(&watcher_handle.state::<AppState>().games);
+                let mut previous = watcher::Snapshot::new();
+                loop {
+                    let state = watcher_handle.state::<AppState>();
+                    match state.platform.processes.list() {
+                        Ok(running) => {
+                            let current = watcher::snapshot(&targets, &running);
+                            let now = Utc::now().to_rfc3339();
+                            for event in watcher::diff(&targets, &previous, &current, &now) {
+                                debug!("{}: {} {:?}", event.name, event.payload.id, event.payload.pids);
+                                let _ = watcher_handle.emit_all(event.name, &event.payload);
+                            }
+                            previous = current;
+                        }
+                        Err(e) => warn!("Failed to list processes: {}", e),
+                    }
+                    thread::sleep(WATCH_INTERVAL);
+                }
+            });
+
             let window = app.get_window("main").unwrap();
-            let state = app.state::<AppState>();
             let settings = state.settings.lock().unwrap();
 
             let args: Vec<String> = env::args().collect();
@@ -998,16 +1076,11 @@ fn main() {
                             let window_handle = window.clone();
                             
                             std::thread::spawn(move || {
-                                #[cfg(target_os = "windows")]
-                                let _ = Command::new("cmd")
-                                    .creation_flags(0x08000000)
-                                    .stdout(Stdio::null())
-                                    .stderr(Stdio::null())
-                                    .spawn();
+                                let state = window_handle.state::<AppState>();
+                                let _ = state.platform.control.spawn_hidden("cmd", &[]);
 
                                 std::thread::sleep(std::time::Duration::from_secs(2));
                                 
-                                let state = window_handle.state::<AppState>();
                                 let last_move = state.last_move_time.load(Ordering::SeqCst);
                                 
                                 if current_time == last_move {
@@ -1018,11 +1091,8 @@ fn main() {
                                         if settings.preferred_monitor != Some(monitor_idx) {
                                             settings.preferred_monitor = Some(monitor_idx);
                                             
-                                            if let Ok(settings_json) = serde_json::to_string_pretty(&*settings) {
-                                                let app_data_dir = path::app_data_dir(&tauri::Config::default()).unwrap();
-                                                let settings_path = app_data_dir.join("Nidalee").join("settings.json");
-                                                let _ = fs::write(settings_path, settings_json);
-                                                println!("Window position saved after confirmed monitor change");
+                                            if state.store.lock().unwrap().save_settings(&settings).is_ok() {
+                                                debug!("Window position saved after confirmed monitor change");
                                             }
                                         }
                                     }
@@ -0,0 +1,331 @@
+use crate::credential_entry::EntryMode;
+use crate::error::NidaleeError;
+use crate::field_location::FieldLocation;
+use crate::launch::LaunchPolicy;
+use crate::logging;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+type Migration = fn(Value) -> Result<Value, String>;
+
+pub struct Schema {
+    pub name: &'static str,
+    migrations: &'static [Migration],
+}
+
+impl Schema {
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+}
+
+// migrations[n] upgrades a file from version n to version n + 1. Files written
+// before schema versioning existed have no schema_version field and count as version 0.
+pub const SETTINGS: Schema = Schema {
+    name: "settings",
+    migrations: &[settings_v0_to_v1, settings_v1_to_v2, settings_v2_to_v3],
+};
+
+pub const ACCOUNTS: Schema = Schema {
+    name: "accounts",
+    migrations: &[accounts_v0_to_v1],
+};
+
+pub const CATEGORIES: Schema = Schema {
+    name: "categories",
+    migrations: &[categories_v0_to_v1],
+};
+
+fn file_version(value: &Value) -> u32 {
+    value
+        .get(SCHEMA_VERSION_FIELD)
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32
+}
+
+pub fn upgrade(schema: &Schema, value: Value) -> Result<(Value, bool), NidaleeError> {
+    let from = file_version(&value);
+    let to = schema.version();
+    if from > to {
+        return Err(NidaleeError::Parse(format!(
+            "{} file has schema version {}, but this version of Nidalee only supports up to {}",
+            schema.name, from, to
+        )));
+    }
+
+    let mut value = value;
+    for (version, migration) in schema.migrations.iter().enumerate().skip(from as usize) {
+        value = migration(value).map_err(|e| {
+            NidaleeError::Parse(format!(
+                "Failed to migrate {} from version {}: {}",
+                schema.name, version, e
+            ))
+        })?;
+    }
+
+    if let Value::Object(map) = &mut value {
+        map.insert(SCHEMA_VERSION_FIELD.to_string(), json!(to));
+    }
+    Ok((value, from < to))
+}
+
+pub fn upgrade_str(schema: &Schema, content: &str) -> Result<(Value, bool), NidaleeError> {
+    let value: Value = serde_json::from_str(content)?;
+    upgrade(schema, value)
+}
+
+fn into_object(value: Value) -> Result<Map<String, Value>, String> {
+    match value {
+        Value::Object(map) => Ok(map),
+        other => Err(format!("expected a JSON object, found {}", other)),
+    }
+}
+
+fn rename_field(map: &mut Map<String, Value>, from: &str, to: &str) {
+    if let Some(value) = map.remove(from) {
+        map.entry(to).or_insert(value);
+    }
+}
+
+// Early builds stored camelCase flags and a window_pos pair before preferred_monitor existed.
+fn settings_v0_to_v1(value: Value) -> Result<Value, String> {
+    let mut map = into_object(value)?;
+
+    rename_field(&mut map, "startWithWindows", "start_with_windows");
+    rename_field(&mut map, "minimizeToTray", "minimize_to_tray");
+    map.remove("window_pos");
+
+    for (field, default) in [
+        ("riot_client_path", json!("")),
+        ("league_path", json!("")),
+        ("valorant_path", json!("")),
+        ("start_with_windows", json!(false)),
+        ("minimize_to_tray", json!(false)),
+        ("minimize_on_game_launch", json!(false)),
+        ("login_delay", json!(5)),
+        ("preferred_monitor", Value::Null),
+    ] {
+        map.entry(field).or_insert(default);
+    }
+
+    Ok(Value::Object(map))
+}
+
+// Version 2 added the idle auto-lock timeout.
+fn settings_v1_to_v2(value: Value) -> Result<Value, String> {
+    let mut map = into_object(value)?;
+    map.entry("auto_lock_minutes").or_insert(json!(15));
+    Ok(Value::Object(map))
+}
+
+fn default_value<T: Default + Serialize>() -> Result<Value, String> {
+    serde_json::to_value(T::default()).map_err(|e| e.to_string())
+}
+
+// Version 3 added the launch policy, credential entry, login field location and
+// logging options.
+fn settings_v2_to_v3(value: Value) -> Result<Value, String> {
+    let mut map = into_object(value)?;
+    for (field, default) in [
+        ("launch", default_value::<LaunchPolicy>()?),
+        ("credential_entry", default_value::<EntryMode>()?),
+        ("field_location", default_value::<FieldLocation>()?),
+        ("log_level", json!(logging::DEFAULT_LEVEL)),
+        ("log_usernames", json!(false)),
+    ] {
+        map.entry(field).or_insert(default);
+    }
+    Ok(Value::Object(map))
+}
+
+// Version 0 was a bare id -> account map without email or login_count. It never
+// counted launches, so the count starts at 0 even for accounts with a last_login.
+fn accounts_v0_to_v1(value: Value) -> Result<Value, String> {
+    let mut accounts = into_object(value)?;
+    accounts.remove(SCHEMA_VERSION_FIELD);
+
+    for account in accounts.values_mut() {
+        let Value::Object(account) = account else {
+            return Err("account entry is not an object".to_string());
+        };
+        account.entry("email").or_insert(Value::Null);
+        account.entry("login_count").or_insert(json!(0));
+    }
+
+    Ok(json!({ "accounts": accounts }))
+}
+
+fn categories_v0_to_v1(value: Value) -> Result<Value, String> {
+    let mut map = into_object(value)?;
+
+    let mut categories: Vec<String> = Vec::new();
+    if let Some(Value::Array(entries)) = map.remove("categories") {
+        for entry in entries {
+            if let Value::String(name) = entry {
+                let name = name.trim().to_string();
+                if !name.is_empty() && !categories.contains(&name) {
+                    categories.push(name);
+                }
+            }
+        }
+    }
+
+    map.insert("categories".to_string(), json!(categories));
+    Ok(Value::Object(map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Settings;
+
+    const SETTINGS_V0: &str = include_str!("../tests/fixtures/migrations/settings_v0.json");
+    const SETTINGS_V1: &str = include_str!("../tests/fixtures/migrations/settings_v1.json");
+    const SETTINGS_V2: &str = include_str!("../tests/fixtures/migrations/settings_v2.json");
+    const ACCOUNTS_V0: &str = include_str!("../tests/fixtures/migrations/accounts_v0.json");
+    const CATEGORIES_V0: &str = include_str!("../tests/fixtures/migrations/categories_v0.json");
+
+    // The fields version 3 adds, as written for a file that has none of them.
+    fn with_v3_fields(expected: Value) -> Value {
+        let mut expected = into_object(expected).unwrap();
+        let Value::Object(added) = settings_v2_to_v3(json!({})).unwrap() else {
+            unreachable!()
+        };
+        expected.extend(added);
+        Value::Object(expected)
+    }
+
+    #[test]
+    fn upgrades_v0_settings() {
+        let (value, upgraded) = upgrade_str(&SETTINGS, SETTINGS_V0).unwrap();
+        assert!(upgraded);
+        assert_eq!(
+            value,
+            with_v3_fields(json!({
+                "schema_version": 3,
+                "riot_client_path": "C:\\Riot Games\\Riot Client\\RiotClientServices.exe",
+                "league_path": "C:\\Riot Games\\League of Legends",
+                "valorant_path": "",
+                "start_with_windows": true,
+                "minimize_to_tray": true,
+                "minimize_on_game_launch": false,
+                "login_delay": 8,
+                "preferred_monitor": null,
+                "auto_lock_minutes": 15
+            }))
+        );
+        serde_json::from_value::<Settings>(value).unwrap();
+    }
+
+    #[test]
+    fn upgrades_v1_settings() {
+        let (value, upgraded) = upgrade_str(&SETTINGS, SETTINGS_V1).unwrap();
+        assert!(upgraded);
+        assert_eq!(
+            value,
+            with_v3_fields(json!({
+                "schema_version": 3,
+                "riot_client_path": "D:\\Riot Games\\Riot Client\\RiotClientServices.exe",
+                "league_path": "D:\\Riot Games\\League of Legends",
+                "valorant_path": "D:\\Riot Games\\VALORANT",
+                "start_with_windows": false,
+                "minimize_to_tray": true,
+                "minimize_on_game_launch": true,
+                "login_delay": 5,
+                "preferred_monitor": 1,
+                "auto_lock_minutes": 15
+            }))
+        );
+    }
+
+    #[test]
+    fn upgrades_v2_settings() {
+        let (value, upgraded) = upgrade_str(&SETTINGS, SETTINGS_V2).unwrap();
+        assert!(upgraded);
+        assert_eq!(value["schema_version"], 3);
+        assert_eq!(value["auto_lock_minutes"], 30);
+        // Set before the bump through the serde defaults, so it is kept.
+        assert_eq!(value["log_level"], "debug");
+        assert_eq!(value["log_usernames"], false);
+        assert_eq!(value["credential_entry"], "typing");
+
+        let settings: Settings = serde_json::from_value(value).unwrap();
+        assert_eq!(settings.launch, LaunchPolicy::default());
+        assert_eq!(settings.field_location, FieldLocation::default());
+        assert_eq!(settings.credential_entry, EntryMode::Typing);
+    }
+
+    #[test]
+    fn current_settings_are_left_alone() {
+        let (current, _) = upgrade_str(&SETTINGS, SETTINGS_V1).unwrap();
+        let (value, upgraded) = upgrade(&SETTINGS, current.clone()).unwrap();
+        assert!(!upgraded);
+        assert_eq!(value, current);
+    }
+
+    #[test]
+    fn rejects_settings_from_a_newer_version() {
+        let newer = json!({ "schema_version": SETTINGS.version() + 1 });
+        assert!(upgrade(&SETTINGS, newer).is_err());
+    }
+
+    #[test]
+    fn upgrades_v0_accounts() {
+        let (value, upgraded) = upgrade_str(&ACCOUNTS, ACCOUNTS_V0).unwrap();
+        assert!(upgraded);
+        assert_eq!(
+            value,
+            json!({
+                "schema_version": 1,
+                "accounts": {
+                    "1700000000000": {
+                        "id": "1700000000000",
+                        "name": "Main",
+                        "username": "fixture_main",
+                        "password": "fixture-password-1",
+                        "email": null,
+                        "category": "Ranked",
+                        "last_login": "2023-11-14T22:13:20+00:00",
+                        "login_count": 0,
+                        "game_type": "league"
+                    },
+                    "1700000000001": {
+                        "id": "1700000000001",
+                        "name": "Smurf",
+                        "username": "fixture_smurf",
+                        "password": "fixture-password-2",
+                        "email": null,
+                        "category": "",
+                        "last_login": null,
+                        "login_count": 0,
+                        "game_type": "valorant"
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_accounts_that_are_not_objects() {
+        assert!(upgrade(&ACCOUNTS, json!({ "1": "not an account" })).is_err());
+        assert!(upgrade(&ACCOUNTS, json!([])).is_err());
+    }
+
+    #[test]
+    fn upgrades_v0_categories() {
+        let (value, upgraded) = upgrade_str(&CATEGORIES, CATEGORIES_V0).unwrap();
+        assert!(upgraded);
+        assert_eq!(
+            value,
+            json!({ "schema_version": 1, "categories": ["Ranked", "Normals"] })
+        );
+    }
+
+    #[test]
+    fn missing_categories_become_empty() {
+        let (value, _) = upgrade(&CATEGORIES, json!({})).unwrap();
+        assert_eq!(value, json!({ "schema_version": 1, "categories": [] }));
+    }
+}
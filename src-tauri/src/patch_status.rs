@@ -0,0 +1,291 @@
+use crate::installs;
+use crate::riot_api::RiotClientApi;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatchState {
+    UpToDate,
+    UpdateAvailable,
+    Updating,
+    NeedsRepair,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PatchStatus {
+    pub product_id: String,
+    pub patchline: String,
+    pub state: PatchState,
+    // 0 to 100, only known while the client is reporting progress.
+    pub percent: Option<f32>,
+}
+
+impl PatchStatus {
+    pub fn is_updating(&self) -> bool {
+        self.state == PatchState::Updating
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallRecord {
+    pub id: String,
+    pub product_id: String,
+    pub patchline: String,
+}
+
+fn text<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .find_map(|key| value.get(*key).and_then(Value::as_str))
+}
+
+fn flag(value: &Value, keys: &[&str]) -> Option<bool> {
+    keys.iter()
+        .find_map(|key| value.get(*key).and_then(Value::as_bool))
+}
+
+// The client has returned both a list and a map keyed by install id over time.
+pub fn parse_installs(body: &str) -> Result<Vec<InstallRecord>, String> {
+    let value: Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let entries: Vec<(Option<&str>, &Value)> = match &value {
+        Value::Array(items) => items.iter().map(|item| (None, item)).collect(),
+        Value::Object(map) => map
+            .iter()
+            .map(|(id, item)| (Some(id.as_str()), item))
+            .collect(),
+        _ => return Err("Unexpected install list from the Riot Client".to_string()),
+    };
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|(key, item)| {
+            Some(InstallRecord {
+                id: text(item, &["id", "installId"]).or(key)?.to_string(),
+                product_id: text(item, &["product_id", "productId"])?.to_string(),
+                patchline: text(item, &["patchline_id", "patchlineId", "patchline"])?.to_string(),
+            })
+        })
+        .collect())
+}
+
+pub fn parse_install_state(body: &str) -> Result<(PatchState, Option<f32>), String> {
+    let value: Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let percent = ["percentPatched", "percent_patched", "progress"]
+        .iter()
+        .find_map(|key| match value.get(*key)? {
+            Value::Object(progress) => progress.get("percent").and_then(Value::as_f64),
+            other => other.as_f64(),
+        })
+        .map(|percent| percent.clamp(0.0, 100.0) as f32);
+    let action = text(&value, &["action"]).unwrap_or_default();
+
+    let state = if flag(&value, &["isCorrupted", "is_corrupted"]) == Some(true) {
+        PatchState::NeedsRepair
+    } else if flag(&value, &["patching", "isPatching", "is_patching"]) == Some(true)
+        || !matches!(action, "" | "Idle" | "idle" | "None" | "none")
+    {
+        PatchState::Updating
+    } else if flag(&value, &["isUpToDate", "is_up_to_date"]) == Some(true) {
+        PatchState::UpToDate
+    } else if flag(&value, &["isUpdateAvailable", "is_update_available"]) == Some(true) {
+        PatchState::UpdateAvailable
+    } else {
+        PatchState::Unknown
+    };
+
+    let percent = match state {
+        PatchState::Updating => percent,
+        PatchState::UpToDate => Some(100.0),
+        _ => None,
+    };
+    Ok((state, percent))
+}
+
+// Asks the client first since only it knows about a running patch. The metadata
+// files can still tell that an install was left broken and needs a repair.
+pub fn detect(
+    api: &dyn RiotClientApi,
+    metadata_dir: Option<&Path>,
+    product_id: &str,
+    patchline: &str,
+) -> PatchStatus {
+    let (state, percent) = match api.patch_state(product_id, patchline) {
+        Ok(Some(found)) => found,
+        Ok(None) => (PatchState::Unknown, None),
+        Err(e) => {
+            warn!("Could not read patch state from the Riot Client: {}", e);
+            (PatchState::Unknown, None)
+        }
+    };
+
+    let state = match (state, metadata_dir) {
+        (PatchState::Unknown, Some(dir)) => {
+            match installs::read_product_settings(dir, product_id, patchline) {
+                Ok(settings) if settings.should_repair => PatchState::NeedsRepair,
+                _ => PatchState::Unknown,
+            }
+        }
+        (state, _) => state,
+    };
+
+    PatchStatus {
+        product_id: product_id.to_string(),
+        patchline: patchline.to_string(),
+        state,
+        percent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::riot_api::SignedInAccount;
+    use std::fs;
+
+    const INSTALLS_MAP: &str = include_str!("../tests/fixtures/riot_api/installs.json");
+    const INSTALLS_LIST: &str = include_str!("../tests/fixtures/patch_status/installs_list.json");
+    const PATCHING: &str = include_str!("../tests/fixtures/riot_api/install_state_patching.json");
+    const UP_TO_DATE: &str =
+        include_str!("../tests/fixtures/riot_api/install_state_up_to_date.json");
+    const PROGRESS: &str =
+        include_str!("../tests/fixtures/patch_status/install_state_progress.json");
+    const CORRUPTED: &str =
+        include_str!("../tests/fixtures/patch_status/install_state_corrupted.json");
+    const UPDATE_AVAILABLE: &str =
+        include_str!("../tests/fixtures/patch_status/install_state_update_available.json");
+    const NEEDS_REPAIR: &str =
+        include_str!("../tests/fixtures/patch_status/needs_repair.product_settings.yaml");
+
+    fn install(id: &str, product_id: &str, patchline: &str) -> InstallRecord {
+        InstallRecord {
+            id: id.to_string(),
+            product_id: product_id.to_string(),
+            patchline: patchline.to_string(),
+        }
+    }
+
+    struct PatchApi(Result<Option<(PatchState, Option<f32>)>, String>);
+
+    impl RiotClientApi for PatchApi {
+        fn session(&self) -> Result<Option<SignedInAccount>, String> {
+            Ok(None)
+        }
+
+        fn is_product_ready(&self, _product_id: &str, _patchline: &str) -> Result<bool, String> {
+            Ok(false)
+        }
+
+        fn sign_out(&self) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn patch_state(
+            &self,
+            _product_id: &str,
+            _patchline: &str,
+        ) -> Result<Option<(PatchState, Option<f32>)>, String> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn parses_installs_keyed_by_id() {
+        assert_eq!(
+            parse_installs(INSTALLS_MAP),
+            Ok(vec![
+                install(
+                    "2f9c5a6e-41b7-4d0e-9a3c-7e15b8d2f604",
+                    "league_of_legends",
+                    "live"
+                ),
+                install("c8e1d037-6a2f-4b95-8f0d-31a7e4c9b256", "valorant", "live"),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_installs_as_a_list() {
+        // The client's own install has no product and is skipped.
+        assert_eq!(
+            parse_installs(INSTALLS_LIST),
+            Ok(vec![
+                install("league_of_legends.live", "league_of_legends", "live"),
+                install("bacon.live", "bacon", "live"),
+            ])
+        );
+        assert_eq!(
+            parse_installs(r#""installs""#),
+            Err("Unexpected install list from the Riot Client".to_string())
+        );
+        assert!(parse_installs("").is_err());
+    }
+
+    #[test]
+    fn parses_install_states() {
+        let cases = [
+            (PATCHING, PatchState::Updating, Some(37.5)),
+            (PROGRESS, PatchState::Updating, Some(64.2)),
+            (UP_TO_DATE, PatchState::UpToDate, Some(100.0)),
+            (CORRUPTED, PatchState::NeedsRepair, None),
+            (UPDATE_AVAILABLE, PatchState::UpdateAvailable, None),
+            (r#"{"action": "Idle"}"#, PatchState::Unknown, None),
+        ];
+        for (body, state, percent) in cases {
+            assert_eq!(parse_install_state(body), Ok((state, percent)), "{}", body);
+        }
+        assert_eq!(
+            parse_install_state(r#"{"patching": true, "percentPatched": 140}"#),
+            Ok((PatchState::Updating, Some(100.0)))
+        );
+    }
+
+    #[test]
+    fn detect_prefers_the_client() {
+        let status = detect(
+            &PatchApi(Ok(Some((PatchState::Updating, Some(12.0))))),
+            None,
+            "valorant",
+            "live",
+        );
+        assert_eq!(
+            status,
+            PatchStatus {
+                product_id: "valorant".to_string(),
+                patchline: "live".to_string(),
+                state: PatchState::Updating,
+                percent: Some(12.0),
+            }
+        );
+        assert!(status.is_updating());
+    }
+
+    #[test]
+    fn detect_falls_back_to_the_metadata() {
+        let metadata = tempfile::tempdir().unwrap();
+        let path = installs::product_settings_path(metadata.path(), "league_of_legends", "live");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, NEEDS_REPAIR).unwrap();
+
+        let unreachable = PatchApi(Err("Riot Client is not running".to_string()));
+        let status = detect(
+            &unreachable,
+            Some(metadata.path()),
+            "league_of_legends",
+            "live",
+        );
+        assert_eq!(status.state, PatchState::NeedsRepair);
+
+        let status = detect(
+            &PatchApi(Ok(None)),
+            Some(metadata.path()),
+            "valorant",
+            "live",
+        );
+        assert_eq!(status.state, PatchState::Unknown);
+        let status = detect(&unreachable, None, "league_of_legends", "live");
+        assert_eq!(status.state, PatchState::Unknown);
+    }
+}
@@ -0,0 +1,281 @@
+use super::{
+    Clipboard, ClipboardSnapshot, Hive, Input, InputKey, Platform, ProcessControl, ProcessInfo,
+    ProcessList, Registry, WindowFinder, WindowHandle, WindowRect,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+// A scripted stand-in for the OS. Tests set up processes and windows in FakeState,
+// run the code under test against platform(), then check what it did in `actions`.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Spawn(String, Vec<String>),
+    RequestClose(u32),
+    Kill(u32),
+    Focus(WindowHandle),
+    MoveMouse(i32, i32),
+    Click,
+    Type(String),
+    Key(InputKey),
+    SetClipboard(String),
+    RestoreClipboard(ClipboardSnapshot),
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeWindow {
+    pub handle: WindowHandle,
+    pub class: String,
+    pub title: String,
+    pub rect: WindowRect,
+    pub pid: u32,
+    pub minimized: bool,
+}
+
+type Hook = Box<dyn FnMut(&mut FakeState) + Send>;
+
+#[derive(Default)]
+pub struct FakeState {
+    pub processes: Vec<ProcessInfo>,
+    pub windows: Vec<FakeWindow>,
+    pub foreground: Option<WindowHandle>,
+    pub registry: HashMap<String, String>,
+    pub clipboard: ClipboardSnapshot,
+    // Set when another program holds the clipboard open.
+    pub clipboard_busy: bool,
+    pub actions: Vec<Action>,
+    // Processes that ignore a close request, and ones that survive being killed.
+    pub ignores_close: HashSet<u32>,
+    pub unkillable: HashSet<u32>,
+    // After this many inputs another window takes the foreground.
+    pub steal_focus_after: Option<usize>,
+    // Runs on every spawn_hidden, e.g. to bring up the client's window.
+    pub on_spawn: Option<Hook>,
+    pub inputs: usize,
+}
+
+impl FakeState {
+    fn window(&self, handle: WindowHandle) -> Option<&FakeWindow> {
+        self.windows.iter().find(|window| window.handle == handle)
+    }
+
+    fn input(&mut self, action: Action) {
+        self.actions.push(action);
+        self.inputs += 1;
+        if self.steal_focus_after == Some(self.inputs) {
+            self.foreground = Some(STOLEN_FOCUS);
+        }
+    }
+}
+
+pub const STOLEN_FOCUS: WindowHandle = WindowHandle(-1);
+// CF_UNICODETEXT, though the fake stores the text as UTF-8.
+pub const TEXT_FORMAT: u32 = 13;
+
+pub fn process(pid: u32, name: &str, parent_pid: Option<u32>) -> ProcessInfo {
+    ProcessInfo {
+        pid,
+        name: name.to_string(),
+        path: None,
+        parent_pid,
+        start_time: 1_700_000_000 + pid as u64,
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FakePlatform {
+    pub state: Arc<Mutex<FakeState>>,
+}
+
+impl FakePlatform {
+    pub fn new(state: FakeState) -> Self {
+        FakePlatform {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    pub fn platform(&self) -> Platform {
+        Platform {
+            processes: Box::new(self.clone()),
+            control: Box::new(self.clone()),
+            windows: Box::new(self.clone()),
+            registry: Box::new(self.clone()),
+            input: Box::new(self.clone()),
+            clipboard: Box::new(self.clone()),
+        }
+    }
+
+    pub fn actions(&self) -> Vec<Action> {
+        self.state.lock().unwrap().actions.clone()
+    }
+
+    pub fn pids(&self) -> Vec<u32> {
+        let state = self.state.lock().unwrap();
+        state.processes.iter().map(|process| process.pid).collect()
+    }
+}
+
+impl ProcessList for FakePlatform {
+    fn list(&self) -> Result<Vec<ProcessInfo>, String> {
+        Ok(self.state.lock().unwrap().processes.clone())
+    }
+}
+
+impl ProcessControl for FakePlatform {
+    fn spawn_hidden(&self, program: &str, args: &[&str]) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        state.actions.push(Action::Spawn(program.to_string(), args));
+        if let Some(mut hook) = state.on_spawn.take() {
+            hook(&mut state);
+            state.on_spawn = Some(hook);
+        }
+        Ok(())
+    }
+
+    fn request_close(&self, pid: u32) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        state.actions.push(Action::RequestClose(pid));
+        if !state.ignores_close.contains(&pid) {
+            state.processes.retain(|process| process.pid != pid);
+        }
+        Ok(())
+    }
+
+    fn kill(&self, pid: u32) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        state.actions.push(Action::Kill(pid));
+        if state.unkillable.contains(&pid) {
+            return Err("Access is denied.".to_string());
+        }
+        state.processes.retain(|process| process.pid != pid);
+        Ok(())
+    }
+}
+
+impl WindowFinder for FakePlatform {
+    fn find(&self, class: Option<&str>, title: Option<&str>) -> Option<WindowHandle> {
+        let state = self.state.lock().unwrap();
+        state
+            .windows
+            .iter()
+            .find(|window| {
+                class.is_none_or(|class| window.class == class)
+                    && title.is_none_or(|title| window.title == title)
+            })
+            .map(|window| window.handle)
+    }
+
+    fn rect(&self, window: WindowHandle) -> Option<WindowRect> {
+        self.state.lock().unwrap().window(window).map(|w| w.rect)
+    }
+
+    fn is_minimized(&self, window: WindowHandle) -> bool {
+        let state = self.state.lock().unwrap();
+        state.window(window).is_some_and(|w| w.minimized)
+    }
+
+    fn foreground(&self) -> Option<WindowHandle> {
+        self.state.lock().unwrap().foreground
+    }
+
+    fn process_id(&self, window: WindowHandle) -> Option<u32> {
+        self.state.lock().unwrap().window(window).map(|w| w.pid)
+    }
+
+    fn focus(&self, window: WindowHandle) {
+        let mut state = self.state.lock().unwrap();
+        state.actions.push(Action::Focus(window));
+        state.foreground = Some(window);
+    }
+
+    fn restore(&self, window: WindowHandle) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(w) = state.windows.iter_mut().find(|w| w.handle == window) {
+            w.minimized = false;
+        }
+    }
+
+    fn hide(&self, _window: WindowHandle) {}
+
+    fn minimize(&self, window: WindowHandle) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(w) = state.windows.iter_mut().find(|w| w.handle == window) {
+            w.minimized = true;
+        }
+    }
+}
+
+fn registry_key(hive: Hive, path: &str, name: &str) -> String {
+    format!("{:?}\\{}\\{}", hive, path, name)
+}
+
+impl Registry for FakePlatform {
+    fn read_string(&self, hive: Hive, path: &str, name: &str) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state.registry.get(&registry_key(hive, path, name)).cloned()
+    }
+
+    fn write_string(&self, hive: Hive, path: &str, name: &str, value: &str) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .registry
+            .insert(registry_key(hive, path, name), value.to_string());
+        Ok(())
+    }
+
+    fn delete_value(&self, hive: Hive, path: &str, name: &str) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        state.registry.remove(&registry_key(hive, path, name));
+        Ok(())
+    }
+}
+
+impl Input for FakePlatform {
+    fn move_mouse(&self, x: i32, y: i32) {
+        self.state.lock().unwrap().input(Action::MoveMouse(x, y));
+    }
+
+    fn left_click(&self) {
+        self.state.lock().unwrap().input(Action::Click);
+    }
+
+    fn type_text(&self, text: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .input(Action::Type(text.to_string()));
+    }
+
+    fn key(&self, key: InputKey) {
+        self.state.lock().unwrap().input(Action::Key(key));
+    }
+}
+
+impl Clipboard for FakePlatform {
+    fn save(&self) -> Result<ClipboardSnapshot, String> {
+        let state = self.state.lock().unwrap();
+        if state.clipboard_busy {
+            return Err("Access is denied.".to_string());
+        }
+        Ok(state.clipboard.clone())
+    }
+
+    fn set_private_text(&self, text: &str) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        state.actions.push(Action::SetClipboard(text.to_string()));
+        state.clipboard = ClipboardSnapshot {
+            formats: vec![(TEXT_FORMAT, text.as_bytes().to_vec())],
+        };
+        Ok(())
+    }
+
+    fn restore(&self, previous: &ClipboardSnapshot) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .actions
+            .push(Action::RestoreClipboard(previous.clone()));
+        state.clipboard = previous.clone();
+        Ok(())
+    }
+}
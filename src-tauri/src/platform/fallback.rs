@@ -0,0 +1,100 @@
+use super::{ClipboardSnapshot, Hive, InputKey, WindowHandle, WindowRect};
+
+// Riot games only ship for Windows. On other targets these keep the app and its
+// launch logic building, and report that nothing can be launched or found.
+
+pub struct NativeControl;
+
+impl super::ProcessControl for NativeControl {
+    fn spawn_hidden(&self, program: &str, _args: &[&str]) -> Result<(), String> {
+        Err(format!("Launching {} is only supported on Windows", program))
+    }
+
+    fn request_close(&self, _pid: u32) -> Result<(), String> {
+        Err("Closing processes is only supported on Windows".to_string())
+    }
+
+    fn kill(&self, _pid: u32) -> Result<(), String> {
+        Err("Closing processes is only supported on Windows".to_string())
+    }
+}
+
+pub struct NativeWindows;
+
+impl super::WindowFinder for NativeWindows {
+    fn find(&self, _class: Option<&str>, _title: Option<&str>) -> Option<WindowHandle> {
+        None
+    }
+
+    fn rect(&self, _window: WindowHandle) -> Option<WindowRect> {
+        None
+    }
+
+    fn is_minimized(&self, _window: WindowHandle) -> bool {
+        false
+    }
+
+    fn foreground(&self) -> Option<WindowHandle> {
+        None
+    }
+
+    fn process_id(&self, _window: WindowHandle) -> Option<u32> {
+        None
+    }
+
+    fn focus(&self, _window: WindowHandle) {}
+
+    fn restore(&self, _window: WindowHandle) {}
+
+    fn hide(&self, _window: WindowHandle) {}
+
+    fn minimize(&self, _window: WindowHandle) {}
+}
+
+pub struct NativeRegistry;
+
+impl super::Registry for NativeRegistry {
+    fn read_string(&self, _hive: Hive, _path: &str, _name: &str) -> Option<String> {
+        None
+    }
+
+    fn write_string(&self, _hive: Hive, _path: &str, _name: &str, _value: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn delete_value(&self, _hive: Hive, _path: &str, _name: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+pub struct NativeInput;
+
+impl super::Input for NativeInput {
+    fn move_mouse(&self, _x: i32, _y: i32) {}
+
+    fn left_click(&self) {}
+
+    fn type_text(&self, _text: &str) {}
+
+    fn key(&self, _key: InputKey) {}
+}
+
+pub struct NativeClipboard;
+
+impl super::Clipboard for NativeClipboard {
+    fn save(&self) -> Result<ClipboardSnapshot, String> {
+        Ok(ClipboardSnapshot::default())
+    }
+
+    fn set_private_text(&self, _text: &str) -> Result<(), String> {
+        Err("Clipboard entry is only supported on Windows".to_string())
+    }
+
+    fn restore(&self, _previous: &ClipboardSnapshot) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+pub fn acquire_single_instance(_name: &str) -> bool {
+    true
+}
@@ -0,0 +1,151 @@
+use super::{Input, InputKey, WindowFinder, WindowHandle};
+use std::cell::Cell;
+
+pub const FOCUS_LOST: &str =
+    "The Riot Client window lost focus, so login was stopped before typing into another window";
+
+// Wraps input injection so nothing is sent unless the target window, or another
+// window of the same process, is in the foreground right before each call.
+pub struct FocusGuard<'a> {
+    windows: &'a dyn WindowFinder,
+    input: &'a dyn Input,
+    target: WindowHandle,
+    target_pid: Option<u32>,
+    lost: Cell<bool>,
+}
+
+impl<'a> FocusGuard<'a> {
+    pub fn new(windows: &'a dyn WindowFinder, input: &'a dyn Input, target: WindowHandle) -> Self {
+        FocusGuard {
+            windows,
+            input,
+            target,
+            target_pid: windows.process_id(target),
+            lost: Cell::new(false),
+        }
+    }
+
+    // Whether a check has failed, so callers can tell a focus loss from other
+    // errors returned through the same calls.
+    pub fn lost_focus(&self) -> bool {
+        self.lost.get()
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        let Some(foreground) = self.windows.foreground() else {
+            return Err(self.lose_focus());
+        };
+        if foreground == self.target {
+            return Ok(());
+        }
+        match (self.target_pid, self.windows.process_id(foreground)) {
+            (Some(target), Some(pid)) if target == pid => Ok(()),
+            _ => Err(self.lose_focus()),
+        }
+    }
+
+    fn lose_focus(&self) -> String {
+        self.lost.set(true);
+        FOCUS_LOST.to_string()
+    }
+
+    pub fn move_mouse(&self, x: i32, y: i32) -> Result<(), String> {
+        self.check()?;
+        self.input.move_mouse(x, y);
+        Ok(())
+    }
+
+    pub fn left_click(&self) -> Result<(), String> {
+        self.check()?;
+        self.input.left_click();
+        Ok(())
+    }
+
+    pub fn type_text(&self, text: &str) -> Result<(), String> {
+        self.check()?;
+        self.input.type_text(text);
+        Ok(())
+    }
+
+    pub fn key(&self, key: InputKey) -> Result<(), String> {
+        self.check()?;
+        self.input.key(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::fake::{Action, FakePlatform, FakeState, FakeWindow, STOLEN_FOCUS};
+    use crate::platform::WindowRect;
+
+    const CLIENT: WindowHandle = WindowHandle(1);
+
+    fn window(handle: WindowHandle, title: &str, pid: u32) -> FakeWindow {
+        FakeWindow {
+            handle,
+            class: "Chrome_WidgetWin_1".to_string(),
+            title: title.to_string(),
+            rect: WindowRect::default(),
+            pid,
+            minimized: false,
+        }
+    }
+
+    fn focused_client(state: FakeState) -> FakePlatform {
+        FakePlatform::new(FakeState {
+            windows: vec![window(CLIENT, "Riot Client", 10)],
+            foreground: Some(CLIENT),
+            ..state
+        })
+    }
+
+    #[test]
+    fn stops_input_once_another_window_takes_focus() {
+        let fake = focused_client(FakeState {
+            steal_focus_after: Some(2),
+            ..FakeState::default()
+        });
+        let guard = FocusGuard::new(&fake, &fake, CLIENT);
+
+        guard.type_text("p").unwrap();
+        guard.type_text("a").unwrap();
+        assert!(!guard.lost_focus());
+        assert_eq!(guard.type_text("s"), Err(FOCUS_LOST.to_string()));
+        assert!(guard.lost_focus());
+        assert_eq!(guard.key(InputKey::Return), Err(FOCUS_LOST.to_string()));
+        assert_eq!(guard.left_click(), Err(FOCUS_LOST.to_string()));
+        assert_eq!(fake.foreground(), Some(STOLEN_FOCUS));
+        assert_eq!(
+            fake.actions(),
+            [Action::Type("p".to_string()), Action::Type("a".to_string())]
+        );
+    }
+
+    #[test]
+    fn accepts_another_window_of_the_same_process() {
+        let fake = focused_client(FakeState::default());
+        let popup = WindowHandle(2);
+        fake.state
+            .lock()
+            .unwrap()
+            .windows
+            .push(window(popup, "Riot Client Popup", 10));
+        let guard = FocusGuard::new(&fake, &fake, CLIENT);
+
+        fake.focus(popup);
+        guard.type_text("p").unwrap();
+        assert!(fake.actions().contains(&Action::Type("p".to_string())));
+    }
+
+    #[test]
+    fn refuses_input_without_a_foreground_window() {
+        let fake = focused_client(FakeState::default());
+        fake.state.lock().unwrap().foreground = None;
+        let guard = FocusGuard::new(&fake, &fake, CLIENT);
+
+        assert_eq!(guard.move_mouse(1, 1), Err(FOCUS_LOST.to_string()));
+        assert!(fake.actions().is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+#[cfg(test)]
+pub mod fake;
+#[cfg(not(windows))]
+mod fallback;
+mod focus;
+mod processes;
+#[cfg(windows)]
+mod win32;
+
+#[cfg(not(windows))]
+use self::fallback as native;
+#[cfg(windows)]
+use self::win32 as native;
+
+use serde::Serialize;
+
+pub use self::focus::{FocusGuard, FOCUS_LOST};
+pub use self::processes::SystemProcesses;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub path: Option<String>,
+    pub parent_pid: Option<u32>,
+    // Seconds since the Unix epoch.
+    pub start_time: u64,
+}
+
+impl ProcessInfo {
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowHandle(pub isize);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WindowRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl WindowRect {
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hive {
+    CurrentUser,
+    LocalMachine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKey {
+    Tab,
+    Return,
+    // Ctrl+V
+    Paste,
+}
+
+pub trait ProcessList: Send + Sync {
+    fn list(&self) -> Result<Vec<ProcessInfo>, String>;
+}
+
+pub trait ProcessControl: Send + Sync {
+    // Starts a program without a console window and without inheriting stdio.
+    fn spawn_hidden(&self, program: &str, args: &[&str]) -> Result<(), String>;
+    // Asks the process to exit, e.g. by closing its windows.
+    fn request_close(&self, pid: u32) -> Result<(), String>;
+    fn kill(&self, pid: u32) -> Result<(), String>;
+}
+
+pub trait WindowFinder: Send + Sync {
+    fn find(&self, class: Option<&str>, title: Option<&str>) -> Option<WindowHandle>;
+    fn rect(&self, window: WindowHandle) -> Option<WindowRect>;
+    fn is_minimized(&self, window: WindowHandle) -> bool;
+    fn foreground(&self) -> Option<WindowHandle>;
+    fn process_id(&self, window: WindowHandle) -> Option<u32>;
+    fn focus(&self, window: WindowHandle);
+    fn restore(&self, window: WindowHandle);
+    fn hide(&self, window: WindowHandle);
+    fn minimize(&self, window: WindowHandle);
+}
+
+pub trait Registry: Send + Sync {
+    fn read_string(&self, hive: Hive, path: &str, name: &str) -> Option<String>;
+    fn write_string(&self, hive: Hive, path: &str, name: &str, value: &str) -> Result<(), String>;
+    fn delete_value(&self, hive: Hive, path: &str, name: &str) -> Result<(), String>;
+}
+
+pub trait Input: Send + Sync {
+    fn move_mouse(&self, x: i32, y: i32);
+    fn left_click(&self);
+    fn type_text(&self, text: &str);
+    fn key(&self, key: InputKey);
+}
+
+// Everything that was on the clipboard, as the raw data of each format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClipboardSnapshot {
+    pub formats: Vec<(u32, Vec<u8>)>,
+}
+
+pub trait Clipboard: Send + Sync {
+    fn save(&self) -> Result<ClipboardSnapshot, String>;
+    // Sets text that clipboard history, cloud sync and clipboard monitors are asked to ignore.
+    fn set_private_text(&self, text: &str) -> Result<(), String>;
+    // Replaces the clipboard with what save returned, leaving it empty if that was nothing.
+    fn restore(&self, previous: &ClipboardSnapshot) -> Result<(), String>;
+}
+
+pub struct Platform {
+    pub processes: Box<dyn ProcessList>,
+    pub control: Box<dyn ProcessControl>,
+    pub windows: Box<dyn WindowFinder>,
+    pub registry: Box<dyn Registry>,
+    pub input: Box<dyn Input>,
+    pub clipboard: Box<dyn Clipboard>,
+}
+
+impl Platform {
+    pub fn native() -> Self {
+        Platform {
+            processes: Box::new(SystemProcesses::default()),
+            control: Box::new(native::NativeControl),
+            windows: Box::new(native::NativeWindows),
+            registry: Box::new(native::NativeRegistry),
+            input: Box::new(native::NativeInput),
+            clipboard: Box::new(native::NativeClipboard),
+        }
+    }
+}
+
+// Returns false when another instance of the app already holds the lock.
+pub fn acquire_single_instance(name: &str) -> bool {
+    native::acquire_single_instance(name)
+}
@@ -0,0 +1,29 @@
+use super::ProcessInfo;
+use std::sync::Mutex;
+use sysinfo::System;
+
+#[derive(Default)]
+pub struct SystemProcesses {
+    system: Mutex<System>,
+}
+
+impl super::ProcessList for SystemProcesses {
+    fn list(&self) -> Result<Vec<ProcessInfo>, String> {
+        let mut system = self.system.lock().unwrap();
+        system.refresh_processes();
+
+        Ok(system
+            .processes()
+            .values()
+            .map(|process| ProcessInfo {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string(),
+                path: process
+                    .exe()
+                    .map(|path| path.to_string_lossy().into_owned()),
+                parent_pid: process.parent().map(|pid| pid.as_u32()),
+                start_time: process.start_time(),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,262 @@
+use super::{ClipboardSnapshot, Hive, InputKey, WindowHandle, WindowRect};
+use clipboard_win::{raw, Clipboard};
+use enigo::{Enigo, Key, KeyboardControllable, MouseButton, MouseControllable};
+use std::ffi::CString;
+use std::os::windows::process::CommandExt;
+use std::process::{Command, Stdio};
+use windows::core::{PCSTR, PCWSTR};
+use windows::Win32::Foundation::{HWND, RECT};
+use windows::Win32::System::Threading::{CreateMutexW, OpenMutexW, MUTEX_ALL_ACCESS};
+use windows::Win32::UI::WindowsAndMessaging::{
+    BringWindowToTop, FindWindowA, GetForegroundWindow, GetWindowRect, GetWindowThreadProcessId,
+    IsIconic, SetForegroundWindow, ShowWindow, SW_HIDE, SW_MINIMIZE, SW_RESTORE, SW_SHOW,
+};
+use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+use winreg::RegKey;
+
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+const CLIPBOARD_OPEN_ATTEMPTS: usize = 10;
+
+// Registered formats that keep clipboard history, cloud clipboard and clipboard
+// managers from recording what we put on the clipboard.
+const PRIVATE_CLIPBOARD_FORMATS: &[&str] = &[
+    "ExcludeClipboardContentFromMonitorProcessing",
+    "CanIncludeInClipboardHistory",
+    "CanUploadToCloudClipboard",
+];
+
+fn hidden_command(program: &str) -> Command {
+    let mut command = Command::new(program);
+    command.creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+fn taskkill(args: &[&str]) -> Result<(), String> {
+    let output = hidden_command("taskkill")
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn hwnd(window: WindowHandle) -> HWND {
+    HWND(window.0)
+}
+
+fn predef(hive: Hive) -> RegKey {
+    match hive {
+        Hive::CurrentUser => RegKey::predef(HKEY_CURRENT_USER),
+        Hive::LocalMachine => RegKey::predef(HKEY_LOCAL_MACHINE),
+    }
+}
+
+pub struct NativeControl;
+
+impl super::ProcessControl for NativeControl {
+    fn spawn_hidden(&self, program: &str, args: &[&str]) -> Result<(), String> {
+        hidden_command(program)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn request_close(&self, pid: u32) -> Result<(), String> {
+        taskkill(&["/PID", &pid.to_string()])
+    }
+
+    fn kill(&self, pid: u32) -> Result<(), String> {
+        taskkill(&["/F", "/PID", &pid.to_string()])
+    }
+}
+
+pub struct NativeWindows;
+
+impl super::WindowFinder for NativeWindows {
+    fn find(&self, class: Option<&str>, title: Option<&str>) -> Option<WindowHandle> {
+        let class = class.and_then(|class| CString::new(class).ok());
+        let title = title.and_then(|title| CString::new(title).ok());
+        let as_pcstr = |value: &Option<CString>| match value {
+            Some(value) => PCSTR::from_raw(value.as_ptr() as *const u8),
+            None => PCSTR::null(),
+        };
+
+        let window = unsafe { FindWindowA(as_pcstr(&class), as_pcstr(&title)) };
+        if window == HWND(0) {
+            None
+        } else {
+            Some(WindowHandle(window.0))
+        }
+    }
+
+    fn rect(&self, window: WindowHandle) -> Option<WindowRect> {
+        let mut rect = RECT::default();
+        if unsafe { GetWindowRect(hwnd(window), &mut rect) }.as_bool() {
+            Some(WindowRect {
+                left: rect.left,
+                top: rect.top,
+                right: rect.right,
+                bottom: rect.bottom,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn is_minimized(&self, window: WindowHandle) -> bool {
+        unsafe { IsIconic(hwnd(window)) }.as_bool()
+    }
+
+    fn foreground(&self) -> Option<WindowHandle> {
+        let window = unsafe { GetForegroundWindow() };
+        if window == HWND(0) {
+            None
+        } else {
+            Some(WindowHandle(window.0))
+        }
+    }
+
+    fn process_id(&self, window: WindowHandle) -> Option<u32> {
+        let mut pid = 0u32;
+        unsafe { GetWindowThreadProcessId(hwnd(window), Some(&mut pid)) };
+        (pid != 0).then_some(pid)
+    }
+
+    fn focus(&self, window: WindowHandle) {
+        unsafe {
+            BringWindowToTop(hwnd(window));
+            SetForegroundWindow(hwnd(window));
+        }
+    }
+
+    fn restore(&self, window: WindowHandle) {
+        unsafe {
+            ShowWindow(hwnd(window), SW_RESTORE);
+            ShowWindow(hwnd(window), SW_SHOW);
+        }
+        self.focus(window);
+    }
+
+    fn hide(&self, window: WindowHandle) {
+        unsafe {
+            ShowWindow(hwnd(window), SW_HIDE);
+        }
+    }
+
+    fn minimize(&self, window: WindowHandle) {
+        unsafe {
+            ShowWindow(hwnd(window), SW_MINIMIZE);
+        }
+    }
+}
+
+pub struct NativeRegistry;
+
+impl super::Registry for NativeRegistry {
+    fn read_string(&self, hive: Hive, path: &str, name: &str) -> Option<String> {
+        predef(hive)
+            .open_subkey(path)
+            .and_then(|key| key.get_value::<String, _>(name))
+            .ok()
+    }
+
+    fn write_string(&self, hive: Hive, path: &str, name: &str, value: &str) -> Result<(), String> {
+        let (key, _) = predef(hive).create_subkey(path).map_err(|e| e.to_string())?;
+        key.set_value(name, &value).map_err(|e| e.to_string())
+    }
+
+    fn delete_value(&self, hive: Hive, path: &str, name: &str) -> Result<(), String> {
+        let (key, _) = predef(hive).create_subkey(path).map_err(|e| e.to_string())?;
+        key.delete_value(name).map_err(|e| e.to_string())
+    }
+}
+
+pub struct NativeInput;
+
+impl super::Input for NativeInput {
+    fn move_mouse(&self, x: i32, y: i32) {
+        Enigo::new().mouse_move_to(x, y);
+    }
+
+    fn left_click(&self) {
+        Enigo::new().mouse_click(MouseButton::Left);
+    }
+
+    fn type_text(&self, text: &str) {
+        Enigo::new().key_sequence(text);
+    }
+
+    fn key(&self, key: InputKey) {
+        let mut enigo = Enigo::new();
+        match key {
+            InputKey::Tab => enigo.key_click(Key::Tab),
+            InputKey::Return => enigo.key_click(Key::Return),
+            InputKey::Paste => {
+                enigo.key_down(Key::Control);
+                enigo.key_click(Key::Layout('v'));
+                enigo.key_up(Key::Control);
+            }
+        }
+    }
+}
+
+fn open_clipboard() -> Result<Clipboard, String> {
+    Clipboard::new_attempts(CLIPBOARD_OPEN_ATTEMPTS).map_err(|e| e.to_string())
+}
+
+pub struct NativeClipboard;
+
+impl super::Clipboard for NativeClipboard {
+    // GDI formats like CF_BITMAP are not memory and can't be copied, but Windows
+    // synthesizes them again from the CF_DIB copy.
+    fn save(&self) -> Result<ClipboardSnapshot, String> {
+        let _clipboard = open_clipboard()?;
+        let formats = raw::EnumFormats::new()
+            .filter_map(|format| {
+                let mut data = Vec::new();
+                raw::get_vec(format, &mut data).ok()?;
+                Some((format, data))
+            })
+            .collect();
+        Ok(ClipboardSnapshot { formats })
+    }
+
+    fn set_private_text(&self, text: &str) -> Result<(), String> {
+        let _clipboard = open_clipboard()?;
+        raw::set_string(text).map_err(|e| e.to_string())?;
+        for name in PRIVATE_CLIPBOARD_FORMATS {
+            if let Some(format) = raw::register_format(name) {
+                let _ = raw::set_without_clear(format.get(), &0u32.to_le_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    fn restore(&self, previous: &ClipboardSnapshot) -> Result<(), String> {
+        let _clipboard = open_clipboard()?;
+        raw::empty().map_err(|e| e.to_string())?;
+        for (format, data) in &previous.formats {
+            raw::set_without_clear(*format, data).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+pub fn acquire_single_instance(name: &str) -> bool {
+    let name_wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        if OpenMutexW(MUTEX_ALL_ACCESS, false, PCWSTR(name_wide.as_ptr())).is_ok() {
+            return false;
+        }
+        let _ = CreateMutexW(None, true, PCWSTR(name_wide.as_ptr()));
+    }
+    true
+}
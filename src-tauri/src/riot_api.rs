@@ -0,0 +1,532 @@
+use crate::patch_status::{self, PatchState};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::Serialize;
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+const NOT_RUNNING: &str = "Riot Client is not running";
+
+// Written by the Riot Client on startup as name:pid:port:password:protocol and
+// removed again when it exits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lockfile {
+    pub name: String,
+    pub pid: u32,
+    pub port: u16,
+    pub password: String,
+    pub protocol: String,
+}
+
+impl Lockfile {
+    pub fn base_url(&self) -> String {
+        format!("{}://127.0.0.1:{}", self.protocol, self.port)
+    }
+}
+
+pub fn lockfile_path() -> Option<PathBuf> {
+    let local_app_data = env::var_os("LOCALAPPDATA")?;
+    Some(
+        PathBuf::from(local_app_data)
+            .join("Riot Games")
+            .join("Riot Client")
+            .join("Config")
+            .join("lockfile"),
+    )
+}
+
+pub fn parse_lockfile(content: &str) -> Result<Lockfile, String> {
+    let parts: Vec<&str> = content.trim().split(':').collect();
+    let [name, pid, port, password, protocol] = parts[..] else {
+        return Err("Riot Client lockfile is malformed".to_string());
+    };
+    Ok(Lockfile {
+        name: name.to_string(),
+        pid: pid
+            .parse()
+            .map_err(|_| format!("Invalid pid in Riot Client lockfile: {}", pid))?,
+        port: port
+            .parse()
+            .map_err(|_| format!("Invalid port in Riot Client lockfile: {}", port))?,
+        password: password.to_string(),
+        protocol: protocol.to_string(),
+    })
+}
+
+pub fn read_lockfile(path: &Path) -> Result<Lockfile, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => parse_lockfile(&content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(NOT_RUNNING.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SignedInAccount {
+    pub puuid: String,
+    pub username: Option<String>,
+    pub game_name: Option<String>,
+    pub tag_line: Option<String>,
+}
+
+pub trait RiotClientApi: Send + Sync {
+    // None when the client is running but nobody is signed in.
+    fn session(&self) -> Result<Option<SignedInAccount>, String>;
+    // True once the client is signed in and can start the product on that patchline.
+    fn is_product_ready(&self, product_id: &str, patchline: &str) -> Result<bool, String>;
+    fn sign_out(&self) -> Result<(), String>;
+    // None when the client has no install of that product and patchline.
+    fn patch_state(
+        &self,
+        product_id: &str,
+        patchline: &str,
+    ) -> Result<Option<(PatchState, Option<f32>)>, String>;
+}
+
+impl SignedInAccount {
+    // Riot login names are case-insensitive.
+    pub fn is_login(&self, username: &str) -> bool {
+        self.username
+            .as_deref()
+            .is_some_and(|signed_in| signed_in.eq_ignore_ascii_case(username.trim()))
+    }
+}
+
+pub struct LocalApi {
+    // The port and password change on every client start, so the lockfile is read per request.
+    lockfile: Option<PathBuf>,
+}
+
+impl LocalApi {
+    pub fn new(lockfile: Option<PathBuf>) -> Self {
+        LocalApi { lockfile }
+    }
+
+    fn get(&self, path: &str) -> Result<(u16, String), String> {
+        self.request("GET", path)
+    }
+
+    // Returns the status code and body, with 4xx/5xx treated as answers rather than errors.
+    fn request(&self, method: &str, path: &str) -> Result<(u16, String), String> {
+        let lockfile = read_lockfile(self.lockfile.as_deref().ok_or(NOT_RUNNING)?)?;
+        let auth = STANDARD.encode(format!("riot:{}", lockfile.password));
+        let response = agent()?
+            .request(method, &format!("{}{}", lockfile.base_url(), path))
+            .set("Authorization", &format!("Basic {}", auth))
+            .set("Accept", "application/json")
+            .call();
+        let response = match response {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(ureq::Error::Transport(e)) => {
+                return Err(format!("Riot Client API is not reachable: {}", e))
+            }
+        };
+        let status = response.status();
+        let body = response.into_string().map_err(|e| e.to_string())?;
+        Ok((status, body))
+    }
+}
+
+impl RiotClientApi for LocalApi {
+    fn session(&self) -> Result<Option<SignedInAccount>, String> {
+        match self.get("/rso-auth/v1/authorization")? {
+            (200, _) => {}
+            (401 | 404, _) => return Ok(None),
+            (status, _) => return Err(format!("Riot Client API returned {}", status)),
+        }
+
+        let (status, body) = self.get("/rso-auth/v1/authorization/userinfo")?;
+        if status != 200 {
+            return Err(format!("Riot Client API returned {} for userinfo", status));
+        }
+        parse_userinfo(&body).map(Some)
+    }
+
+    fn is_product_ready(&self, product_id: &str, patchline: &str) -> Result<bool, String> {
+        if self.session()?.is_none() {
+            return Ok(false);
+        }
+        let path = format!(
+            "/product-launcher/v1/products/{}/patchlines/{}",
+            product_id, patchline
+        );
+        match self.get(&path)? {
+            (200, _) => Ok(true),
+            (404, _) => Ok(false),
+            (status, _) => Err(format!("Riot Client API returned {}", status)),
+        }
+    }
+
+    fn sign_out(&self) -> Result<(), String> {
+        match self.request("DELETE", "/rso-auth/v1/session")? {
+            (200..=299 | 404, _) => Ok(()),
+            (status, _) => Err(format!("Riot Client API returned {} for sign out", status)),
+        }
+    }
+
+    fn patch_state(
+        &self,
+        product_id: &str,
+        patchline: &str,
+    ) -> Result<Option<(PatchState, Option<f32>)>, String> {
+        let (status, body) = self.get("/patch/v1/installs")?;
+        if status != 200 {
+            return Err(format!("Riot Client API returned {} for installs", status));
+        }
+        let Some(install) = patch_status::parse_installs(&body)?
+            .into_iter()
+            .find(|install| install.product_id == product_id && install.patchline == patchline)
+        else {
+            return Ok(None);
+        };
+
+        match self.get(&format!("/patch/v1/installs/{}/state", install.id))? {
+            (200, body) => patch_status::parse_install_state(&body).map(Some),
+            (404, _) => Ok(None),
+            (status, _) => Err(format!(
+                "Riot Client API returned {} for install state",
+                status
+            )),
+        }
+    }
+}
+
+// The client is only reachable on loopback with a self-signed certificate, so
+// certificate checks are skipped for these requests only.
+fn agent() -> Result<ureq::Agent, String> {
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(ureq::AgentBuilder::new()
+        .tls_connector(Arc::new(connector))
+        .timeout(REQUEST_TIMEOUT)
+        .build())
+}
+
+// Depending on the client version userInfo is either an object or a JSON string.
+pub fn parse_userinfo(body: &str) -> Result<SignedInAccount, String> {
+    let value: Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let info = match value.get("userInfo") {
+        Some(Value::String(inner)) => serde_json::from_str(inner).map_err(|e| e.to_string())?,
+        Some(inner) => inner.clone(),
+        None => value,
+    };
+
+    let text = |value: Option<&Value>| {
+        value
+            .and_then(Value::as_str)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let puuid = text(info.get("sub")).ok_or("Signed-in account has no PUUID")?;
+    let acct = info.get("acct");
+    Ok(SignedInAccount {
+        puuid,
+        username: text(info.get("username"))
+            .or_else(|| text(info.get("preferred_username")))
+            .or_else(|| text(acct.and_then(|acct| acct.get("username")))),
+        game_name: text(acct.and_then(|acct| acct.get("game_name"))),
+        tag_line: text(acct.and_then(|acct| acct.get("tag_line"))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+    use std::thread;
+
+    const AUTHORIZATION: &str = include_str!("../tests/fixtures/riot_api/authorization.json");
+    const USERINFO: &str = include_str!("../tests/fixtures/riot_api/userinfo.json");
+    const USERINFO_OBJECT: &str = include_str!("../tests/fixtures/riot_api/userinfo_object.json");
+    const INSTALLS: &str = include_str!("../tests/fixtures/riot_api/installs.json");
+    const PATCHING: &str = include_str!("../tests/fixtures/riot_api/install_state_patching.json");
+
+    const PASSWORD: &str = "fY3kQ9x-_bLw2mVz8RtJpA";
+
+    // (method and path, status, body)
+    type Route = (&'static str, u16, &'static str);
+
+    // Plays the Riot Client on loopback, answering each request from `routes` and
+    // 404 for anything else. Requests are recorded with their Authorization header.
+    struct MockClient {
+        lockfile: tempfile::NamedTempFile,
+        requests: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl MockClient {
+        fn serve(routes: Vec<Route>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let recorded = requests.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut request = String::new();
+                    reader.read_line(&mut request).unwrap();
+                    let request = request.rsplit_once(' ').unwrap().0.to_string();
+                    let mut auth = String::new();
+                    loop {
+                        let mut header = String::new();
+                        reader.read_line(&mut header).unwrap();
+                        if header.trim().is_empty() {
+                            break;
+                        }
+                        if let Some(value) = header.strip_prefix("Authorization: ") {
+                            auth = value.trim().to_string();
+                        }
+                    }
+
+                    let (status, body) = routes
+                        .iter()
+                        .find(|(route, _, _)| *route == request)
+                        .map_or((404, ""), |(_, status, body)| (*status, *body));
+                    recorded.lock().unwrap().push((request, auth));
+                    write!(
+                        stream,
+                        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    )
+                    .unwrap();
+                }
+            });
+
+            let mut lockfile = tempfile::NamedTempFile::new().unwrap();
+            write!(lockfile, "Riot Client:18424:{}:{}:http", port, PASSWORD).unwrap();
+            MockClient { lockfile, requests }
+        }
+
+        fn api(&self) -> LocalApi {
+            LocalApi::new(Some(self.lockfile.path().to_path_buf()))
+        }
+
+        fn requests(&self) -> Vec<String> {
+            let requests = self.requests.lock().unwrap();
+            requests
+                .iter()
+                .map(|(request, _)| request.clone())
+                .collect()
+        }
+    }
+
+    fn signed_in() -> Vec<Route> {
+        vec![
+            ("GET /rso-auth/v1/authorization", 200, AUTHORIZATION),
+            ("GET /rso-auth/v1/authorization/userinfo", 200, USERINFO),
+        ]
+    }
+
+    #[test]
+    fn parses_the_lockfile() {
+        let lockfile = parse_lockfile("Riot Client:18424:53870:fY3kQ9x-_bLw2mVz8RtJpA:https\r\n");
+        assert_eq!(
+            lockfile,
+            Ok(Lockfile {
+                name: "Riot Client".to_string(),
+                pid: 18424,
+                port: 53870,
+                password: PASSWORD.to_string(),
+                protocol: "https".to_string(),
+            })
+        );
+        assert_eq!(lockfile.unwrap().base_url(), "https://127.0.0.1:53870");
+    }
+
+    #[test]
+    fn rejects_a_broken_lockfile() {
+        assert_eq!(
+            parse_lockfile("Riot Client:18424:53870"),
+            Err("Riot Client lockfile is malformed".to_string())
+        );
+        assert_eq!(
+            parse_lockfile("Riot Client:18424:70000:password:https"),
+            Err("Invalid port in Riot Client lockfile: 70000".to_string())
+        );
+        assert_eq!(
+            parse_lockfile("Riot Client:pid:53870:password:https"),
+            Err("Invalid pid in Riot Client lockfile: pid".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_lockfile_means_the_client_is_not_running() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            read_lockfile(&dir.path().join("lockfile")),
+            Err(NOT_RUNNING.to_string())
+        );
+        assert_eq!(LocalApi::new(None).session(), Err(NOT_RUNNING.to_string()));
+    }
+
+    #[test]
+    fn parses_userinfo_sent_as_a_string() {
+        assert_eq!(
+            parse_userinfo(USERINFO),
+            Ok(SignedInAccount {
+                puuid: "3f1c6e0a-9d2b-5c47-8e1a-6b0d4f2c9a71".to_string(),
+                username: Some("NidaleeMain".to_string()),
+                game_name: Some("Nidalee Main".to_string()),
+                tag_line: Some("EUW".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn parses_userinfo_sent_as_an_object() {
+        // Empty names fall through to the next place a name can be.
+        assert_eq!(
+            parse_userinfo(USERINFO_OBJECT),
+            Ok(SignedInAccount {
+                puuid: "8a27d4c1-50fe-5b3e-b6a9-0c4e7f1d2b38".to_string(),
+                username: Some("PounceSmurf".to_string()),
+                game_name: Some("Pounce".to_string()),
+                tag_line: None,
+            })
+        );
+        assert_eq!(
+            parse_userinfo(r#"{"acct": {"username": "NoSubject"}}"#),
+            Err("Signed-in account has no PUUID".to_string())
+        );
+        assert!(parse_userinfo("<html>").is_err());
+    }
+
+    #[test]
+    fn logins_match_case_insensitively() {
+        let account = parse_userinfo(USERINFO).unwrap();
+        assert!(account.is_login("nidaleemain "));
+        assert!(!account.is_login("NidaleeMain2"));
+    }
+
+    #[test]
+    fn reads_the_signed_in_account() {
+        let client = MockClient::serve(signed_in());
+        let account = client.api().session().unwrap().unwrap();
+        assert_eq!(account.username.as_deref(), Some("NidaleeMain"));
+
+        let auth = format!("Basic {}", STANDARD.encode(format!("riot:{}", PASSWORD)));
+        let requests = client.requests.lock().unwrap();
+        assert!(requests.iter().all(|(_, sent)| *sent == auth));
+    }
+
+    #[test]
+    fn reports_a_signed_out_client() {
+        let client = MockClient::serve(vec![]);
+        assert_eq!(client.api().session(), Ok(None));
+        assert_eq!(client.api().is_product_ready("valorant", "live"), Ok(false));
+        assert_eq!(client.requests(), ["GET /rso-auth/v1/authorization"; 2]);
+    }
+
+    #[test]
+    fn surfaces_unexpected_statuses() {
+        let client = MockClient::serve(vec![(
+            "GET /rso-auth/v1/authorization",
+            503,
+            r#"{"errorCode": "RPC_ERROR"}"#,
+        )]);
+        assert_eq!(
+            client.api().session(),
+            Err("Riot Client API returned 503".to_string())
+        );
+
+        let client = MockClient::serve(vec![
+            ("GET /rso-auth/v1/authorization", 200, AUTHORIZATION),
+            ("GET /rso-auth/v1/authorization/userinfo", 401, ""),
+        ]);
+        assert_eq!(
+            client.api().session(),
+            Err("Riot Client API returned 401 for userinfo".to_string())
+        );
+    }
+
+    #[test]
+    fn checks_whether_the_product_can_launch() {
+        let mut routes = signed_in();
+        routes.push((
+            "GET /product-launcher/v1/products/valorant/patchlines/live",
+            200,
+            "{}",
+        ));
+        let client = MockClient::serve(routes);
+        assert_eq!(client.api().is_product_ready("valorant", "live"), Ok(true));
+        assert_eq!(client.api().is_product_ready("valorant", "pbe"), Ok(false));
+    }
+
+    #[test]
+    fn signs_out() {
+        let client = MockClient::serve(vec![("DELETE /rso-auth/v1/session", 204, "")]);
+        assert_eq!(client.api().sign_out(), Ok(()));
+        assert_eq!(client.requests(), ["DELETE /rso-auth/v1/session"]);
+
+        // Nobody was signed in.
+        let client = MockClient::serve(vec![]);
+        assert_eq!(client.api().sign_out(), Ok(()));
+
+        let client = MockClient::serve(vec![("DELETE /rso-auth/v1/session", 500, "")]);
+        assert_eq!(
+            client.api().sign_out(),
+            Err("Riot Client API returned 500 for sign out".to_string())
+        );
+    }
+
+    #[test]
+    fn reads_the_patch_state_of_an_install() {
+        let client = MockClient::serve(vec![
+            ("GET /patch/v1/installs", 200, INSTALLS),
+            (
+                "GET /patch/v1/installs/c8e1d037-6a2f-4b95-8f0d-31a7e4c9b256/state",
+                200,
+                PATCHING,
+            ),
+        ]);
+        assert_eq!(
+            client.api().patch_state("valorant", "live"),
+            Ok(Some((PatchState::Updating, Some(37.5))))
+        );
+        // No install for that patchline, so its state is never asked for.
+        assert_eq!(client.api().patch_state("valorant", "pbe"), Ok(None));
+        // The install is listed but the client has no state for it.
+        assert_eq!(
+            client.api().patch_state("league_of_legends", "live"),
+            Ok(None)
+        );
+        assert_eq!(
+            client.requests(),
+            [
+                "GET /patch/v1/installs",
+                "GET /patch/v1/installs/c8e1d037-6a2f-4b95-8f0d-31a7e4c9b256/state",
+                "GET /patch/v1/installs",
+                "GET /patch/v1/installs",
+                "GET /patch/v1/installs/2f9c5a6e-41b7-4d0e-9a3c-7e15b8d2f604/state",
+            ]
+        );
+    }
+
+    #[test]
+    fn client_that_stopped_answering() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut lockfile = tempfile::NamedTempFile::new().unwrap();
+        write!(lockfile, "Riot Client:18424:{}:{}:http", port, PASSWORD).unwrap();
+
+        let result = LocalApi::new(Some(lockfile.path().to_path_buf())).session();
+        assert!(result
+            .unwrap_err()
+            .starts_with("Riot Client API is not reachable"));
+    }
+}
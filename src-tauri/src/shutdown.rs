@@ -0,0 +1,382 @@
+use crate::error::NidaleeError;
+use crate::games::{GameRegistry, RIOT_CLIENT_PROCESSES};
+use crate::platform::{Platform, ProcessControl, ProcessInfo, ProcessList};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+pub struct CloseOptions {
+    pub graceful_timeout: Duration,
+    pub kill_timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for CloseOptions {
+    fn default() -> Self {
+        CloseOptions {
+            graceful_timeout: Duration::from_secs(5),
+            kill_timeout: Duration::from_secs(3),
+            poll_interval: Duration::from_millis(250),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct CloseReport {
+    pub closed: Vec<ProcessInfo>,
+    pub killed: Vec<ProcessInfo>,
+    pub survived: Vec<ProcessInfo>,
+}
+
+// Returns the roots and everything below them, parents before children.
+pub fn descendants(running: &[ProcessInfo], roots: &[u32]) -> Vec<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for process in running {
+        if let Some(parent) = process.parent_pid {
+            children.entry(parent).or_default().push(process.pid);
+        }
+    }
+
+    let mut seen: HashSet<u32> = HashSet::new();
+    let mut ordered = Vec::new();
+    let mut queue: Vec<u32> = roots.to_vec();
+    while !queue.is_empty() {
+        let mut next = Vec::new();
+        for pid in queue {
+            if seen.insert(pid) {
+                ordered.push(pid);
+                next.extend(children.get(&pid).into_iter().flatten().copied());
+            }
+        }
+        queue = next;
+    }
+    ordered
+}
+
+// Picks the game's processes and their children. The Riot Client and its tree are
+// only included when keep_client is false, i.e. no other game still depends on it.
+// The result is ordered children first so parents are not asked to close before them.
+pub fn select_targets(
+    running: &[ProcessInfo],
+    game_processes: &[impl AsRef<str>],
+    client_processes: &[impl AsRef<str>],
+    keep_client: bool,
+) -> Vec<ProcessInfo> {
+    let is_client = |process: &ProcessInfo| {
+        client_processes
+            .iter()
+            .any(|name| process.is_named(name.as_ref()))
+    };
+    let roots: Vec<u32> = running
+        .iter()
+        .filter(|process| {
+            game_processes
+                .iter()
+                .any(|name| process.is_named(name.as_ref()))
+                || (!keep_client && is_client(process))
+        })
+        .map(|process| process.pid)
+        .collect();
+
+    let by_pid: HashMap<u32, &ProcessInfo> = running
+        .iter()
+        .map(|process| (process.pid, process))
+        .collect();
+    let mut targets: Vec<ProcessInfo> = descendants(running, &roots)
+        .into_iter()
+        .filter_map(|pid| by_pid.get(&pid).copied())
+        .filter(|process| !(keep_client && is_client(process)))
+        .cloned()
+        .collect();
+    targets.reverse();
+    targets
+}
+
+fn same_process(a: &ProcessInfo, b: &ProcessInfo) -> bool {
+    a.pid == b.pid && a.start_time == b.start_time
+}
+
+// A PID only counts as alive if it still belongs to the process we targeted, so a
+// recycled PID is never killed by mistake.
+fn still_running(
+    processes: &dyn ProcessList,
+    targets: &[ProcessInfo],
+) -> Result<Vec<ProcessInfo>, String> {
+    let running = processes.list()?;
+    Ok(targets
+        .iter()
+        .filter(|target| running.iter().any(|process| same_process(process, target)))
+        .cloned()
+        .collect())
+}
+
+// For closes nobody can call off, like the user's own close button.
+fn sleep(duration: Duration) -> Result<(), String> {
+    thread::sleep(duration);
+    Ok(())
+}
+
+// Time is counted in pauses rather than read from a clock, so a caller's pause
+// decides both how long the wait takes and whether it is given up.
+fn wait_for_exit(
+    processes: &dyn ProcessList,
+    targets: &[ProcessInfo],
+    timeout: Duration,
+    poll_interval: Duration,
+    pause: &dyn Fn(Duration) -> Result<(), String>,
+) -> Result<Vec<ProcessInfo>, String> {
+    let poll_interval = poll_interval.max(Duration::from_millis(10));
+    let mut waited = Duration::ZERO;
+    loop {
+        let alive = still_running(processes, targets)?;
+        if alive.is_empty() || waited >= timeout {
+            return Ok(alive);
+        }
+        pause(poll_interval)?;
+        waited += poll_interval;
+    }
+}
+
+// `pause` sleeps between checks and fails once the close is no longer wanted, in
+// which case nothing further is closed or killed.
+pub fn close_tree(
+    processes: &dyn ProcessList,
+    control: &dyn ProcessControl,
+    targets: &[ProcessInfo],
+    options: &CloseOptions,
+    pause: &dyn Fn(Duration) -> Result<(), String>,
+) -> Result<CloseReport, String> {
+    let mut report = CloseReport::default();
+    if targets.is_empty() {
+        return Ok(report);
+    }
+
+    for target in targets {
+        debug!("Closing process: {} ({})", target.name, target.pid);
+        if let Err(e) = control.request_close(target.pid) {
+            warn!("Graceful close of {} failed: {}", target.pid, e);
+        }
+    }
+    let remaining = wait_for_exit(
+        processes,
+        targets,
+        options.graceful_timeout,
+        options.poll_interval,
+        pause,
+    )?;
+    report.closed = targets
+        .iter()
+        .filter(|target| !remaining.iter().any(|alive| same_process(alive, target)))
+        .cloned()
+        .collect();
+
+    for target in &remaining {
+        debug!("Force closing process: {} ({})", target.name, target.pid);
+        if let Err(e) = control.kill(target.pid) {
+            warn!("Failed to kill {}: {}", target.pid, e);
+        }
+    }
+    let survivors = wait_for_exit(
+        processes,
+        &remaining,
+        options.kill_timeout,
+        options.poll_interval,
+        pause,
+    )?;
+    report.killed = remaining
+        .iter()
+        .filter(|target| !survivors.iter().any(|alive| same_process(alive, target)))
+        .cloned()
+        .collect();
+    report.survived = survivors;
+
+    Ok(report)
+}
+
+// Closes the game and, unless another game still needs it, the Riot Client.
+pub fn close_game(
+    platform: &Platform,
+    games: &GameRegistry,
+    game_id: &str,
+    options: &CloseOptions,
+) -> Result<CloseReport, NidaleeError> {
+    let game = games
+        .get(game_id)
+        .ok_or_else(|| NidaleeError::UnknownGame(game_id.to_string()))?;
+
+    debug!("Attempting to close {} processes...", game.display_name);
+
+    let running = platform.processes.list()?;
+    let close_list = game.close_list();
+    let keep_client = games
+        .all()
+        .iter()
+        .filter(|other| other.id != game.id)
+        .any(|other| {
+            running.iter().any(|process| {
+                other.is_game_process(process)
+                    && !close_list.iter().any(|name| process.is_named(name))
+            })
+        });
+    if keep_client {
+        info!("Another game is still running, leaving the Riot Client open");
+    }
+
+    let targets = select_targets(&running, close_list, RIOT_CLIENT_PROCESSES, keep_client);
+    let report = close_tree(
+        platform.processes.as_ref(),
+        platform.control.as_ref(),
+        &targets,
+        options,
+        &sleep,
+    )?;
+
+    info!(
+        "Closed {}, killed {}, survived {}",
+        report.closed.len(),
+        report.killed.len(),
+        report.survived.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::fake::{process, Action, FakePlatform, FakeState};
+
+    // Nothing waits, so a process that ignores a request is handled on the next step.
+    const NO_WAIT: CloseOptions = CloseOptions {
+        graceful_timeout: Duration::ZERO,
+        kill_timeout: Duration::ZERO,
+        poll_interval: Duration::ZERO,
+    };
+
+    fn league_and_client() -> Vec<ProcessInfo> {
+        vec![
+            process(10, "RiotClientServices.exe", None),
+            process(11, "RiotClientUx.exe", Some(10)),
+            process(20, "LeagueClient.exe", Some(10)),
+            process(21, "LeagueClientUx.exe", Some(20)),
+            process(22, "League of Legends.exe", Some(21)),
+            process(30, "explorer.exe", None),
+        ]
+    }
+
+    fn pids(processes: &[ProcessInfo]) -> Vec<u32> {
+        processes.iter().map(|process| process.pid).collect()
+    }
+
+    #[test]
+    fn closes_the_game_and_the_client_children_first() {
+        let fake = FakePlatform::new(FakeState {
+            processes: league_and_client(),
+            ..FakeState::default()
+        });
+        let report = close_game(
+            &fake.platform(),
+            &GameRegistry::builtin(),
+            "league",
+            &NO_WAIT,
+        )
+        .unwrap();
+
+        assert_eq!(
+            fake.actions(),
+            [22, 21, 20, 11, 10].map(Action::RequestClose)
+        );
+        assert_eq!(pids(&report.closed), [22, 21, 20, 11, 10]);
+        assert!(report.killed.is_empty() && report.survived.is_empty());
+        assert_eq!(fake.pids(), [30]);
+    }
+
+    #[test]
+    fn leaves_the_client_for_another_running_game() {
+        let mut processes = league_and_client();
+        processes.push(process(40, "VALORANT.exe", Some(10)));
+        let fake = FakePlatform::new(FakeState {
+            processes,
+            ..FakeState::default()
+        });
+        let report = close_game(
+            &fake.platform(),
+            &GameRegistry::builtin(),
+            "league",
+            &NO_WAIT,
+        )
+        .unwrap();
+
+        assert_eq!(pids(&report.closed), [22, 21, 20]);
+        assert_eq!(fake.pids(), [10, 11, 30, 40]);
+    }
+
+    #[test]
+    fn kills_what_ignores_the_close_request() {
+        let fake = FakePlatform::new(FakeState {
+            processes: league_and_client(),
+            ignores_close: [22, 10].into(),
+            unkillable: [10].into(),
+            ..FakeState::default()
+        });
+        let report = close_game(
+            &fake.platform(),
+            &GameRegistry::builtin(),
+            "league",
+            &NO_WAIT,
+        )
+        .unwrap();
+
+        assert_eq!(pids(&report.closed), [21, 20, 11]);
+        assert_eq!(pids(&report.killed), [22]);
+        assert_eq!(pids(&report.survived), [10]);
+        assert!(fake
+            .actions()
+            .ends_with(&[Action::Kill(22), Action::Kill(10)]));
+    }
+
+    #[test]
+    fn never_kills_a_recycled_pid() {
+        let fake = FakePlatform::new(FakeState {
+            processes: vec![process(22, "League of Legends.exe", None)],
+            ignores_close: [22].into(),
+            ..FakeState::default()
+        });
+        let targets = fake.list().unwrap();
+        // The game exits and an unrelated process gets its PID.
+        fake.state.lock().unwrap().processes = vec![ProcessInfo {
+            start_time: 1,
+            ..process(22, "notepad.exe", None)
+        }];
+
+        let report = close_tree(&fake, &fake, &targets, &NO_WAIT, &sleep).unwrap();
+        assert_eq!(pids(&report.closed), [22]);
+        assert!(!fake.actions().contains(&Action::Kill(22)));
+    }
+
+    #[test]
+    fn stops_waiting_once_cancelled() {
+        let fake = FakePlatform::new(FakeState {
+            processes: vec![process(22, "League of Legends.exe", None)],
+            ignores_close: [22].into(),
+            ..FakeState::default()
+        });
+        let targets = fake.list().unwrap();
+        let cancelled = |_| Err("Launch cancelled".to_string());
+
+        let result = close_tree(&fake, &fake, &targets, &CloseOptions::default(), &cancelled);
+        assert_eq!(result.unwrap_err(), "Launch cancelled");
+        assert_eq!(fake.actions(), [Action::RequestClose(22)]);
+    }
+
+    #[test]
+    fn unknown_game() {
+        let fake = FakePlatform::default();
+        let result = close_game(&fake.platform(), &GameRegistry::builtin(), "tft", &NO_WAIT);
+        assert_eq!(
+            result.unwrap_err(),
+            NidaleeError::UnknownGame("tft".to_string())
+        );
+    }
+}
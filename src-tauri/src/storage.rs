@@ -0,0 +1,286 @@
+use chrono::Utc;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+pub const BACKUP_COUNT: usize = 5;
+const BACKUP_DIR: &str = "backups";
+
+fn file_name(path: &Path) -> Result<String, String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.to_string())
+        .ok_or_else(|| format!("Invalid storage path: {}", path.display()))
+}
+
+fn backup_dir(path: &Path) -> PathBuf {
+    path.parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(BACKUP_DIR)
+}
+
+fn sync_dir(_dir: &Path) {
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(_dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let tmp_path = path.with_file_name(format!("{}.tmp", file_name(path)?));
+
+    let result = (|| {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp_path, path)
+    })();
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(format!("Failed to write {}: {}", path.display(), e));
+    }
+
+    if let Some(parent) = path.parent() {
+        sync_dir(parent);
+    }
+    Ok(())
+}
+
+pub fn new_backup_path(path: &Path) -> Result<PathBuf, String> {
+    let name = file_name(path)?;
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let timestamp = Utc::now().format("%Y%m%dT%H%M%S%3fZ");
+    Ok(dir.join(format!("{}.{}.bak", name, timestamp)))
+}
+
+pub fn prune_backups(path: &Path) {
+    for stale in list_backups(path).into_iter().skip(BACKUP_COUNT) {
+        let _ = fs::remove_file(stale);
+    }
+}
+
+pub fn list_backups(path: &Path) -> Vec<PathBuf> {
+    let Ok(name) = file_name(path) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name);
+
+    let mut backups: Vec<PathBuf> = fs::read_dir(backup_dir(path))
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .map(|n| n.starts_with(&prefix) && n.ends_with(".bak"))
+                        .unwrap_or(false)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    backups.sort();
+    backups.reverse();
+    backups
+}
+
+pub fn quarantine(path: &Path) {
+    if !path.exists() {
+        return;
+    }
+    if let Ok(name) = file_name(path) {
+        let corrupt_path = path.with_file_name(format!(
+            "{}.corrupt-{}",
+            name,
+            Utc::now().format("%Y%m%dT%H%M%SZ")
+        ));
+        let _ = fs::rename(path, corrupt_path);
+    }
+}
+
+pub fn load<T, E: fmt::Display>(path: &Path, parse: impl Fn(&str) -> Result<T, E>) -> Option<T> {
+    let main_error = match fs::read_to_string(path) {
+        Ok(content) => match parse(&content) {
+            Ok(value) => return Some(value),
+            Err(e) => e.to_string(),
+        },
+        Err(e) => e.to_string(),
+    };
+
+    let backups = list_backups(path);
+    if backups.is_empty() {
+        return None;
+    }
+    warn!(
+        "Failed to load {} ({}), trying backups...",
+        path.display(),
+        main_error
+    );
+
+    for backup_path in backups {
+        let Ok(content) = fs::read_to_string(&backup_path) else {
+            continue;
+        };
+        match parse(&content) {
+            Ok(value) => {
+                info!("Recovered {} from {}", path.display(), backup_path.display());
+                quarantine(path);
+                if let Err(e) = write_atomic(path, content.as_bytes()) {
+                    warn!("Failed to restore {}: {}", path.display(), e);
+                }
+                return Some(value);
+            }
+            Err(e) => warn!("Backup {} is not usable: {}", backup_path.display(), e),
+        }
+    }
+
+    warn!("No valid backup found for {}", path.display());
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap().to_string())
+            .collect()
+    }
+
+    fn write_backup(path: &Path, timestamp: &str, contents: &str) {
+        let dir = backup_dir(path);
+        fs::create_dir_all(&dir).unwrap();
+        let name = format!("{}.{}.bak", file_name(path).unwrap(), timestamp);
+        fs::write(dir.join(name), contents).unwrap();
+    }
+
+    fn parse_number(contents: &str) -> Result<u32, std::num::ParseIntError> {
+        contents.trim().parse()
+    }
+
+    #[test]
+    fn replaces_the_file_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn leaves_no_partial_file_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        // A non-empty directory in the way makes the final rename fail.
+        let path = dir.path().join("settings.json");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("keep"), "keep").unwrap();
+
+        assert!(write_atomic(&path, b"contents").is_err());
+        assert!(!dir.path().join("settings.json.tmp").exists());
+        assert_eq!(fs::read_to_string(path.join("keep")).unwrap(), "keep");
+    }
+
+    #[test]
+    fn lists_backups_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        write_backup(&path, "20260101T000000000Z", "1");
+        write_backup(&path, "20260301T000000000Z", "3");
+        write_backup(&path, "20260201T000000000Z", "2");
+        let other = dir.path().join("accounts.json");
+        write_backup(&other, "20260401T000000000Z", "4");
+        fs::write(backup_dir(&path).join("settings.json.tmp"), "").unwrap();
+
+        assert_eq!(
+            names(&list_backups(&path)),
+            [
+                "settings.json.20260301T000000000Z.bak",
+                "settings.json.20260201T000000000Z.bak",
+                "settings.json.20260101T000000000Z.bak",
+            ]
+        );
+        assert!(list_backups(&dir.path().join("games.json")).is_empty());
+    }
+
+    #[test]
+    fn prunes_to_the_newest_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        for day in 1..=BACKUP_COUNT + 2 {
+            write_backup(&path, &format!("202601{:02}T000000000Z", day), "1");
+        }
+
+        prune_backups(&path);
+        let kept = names(&list_backups(&path));
+        assert_eq!(kept.len(), BACKUP_COUNT);
+        assert_eq!(kept[0], "settings.json.20260107T000000000Z.bak");
+        assert_eq!(
+            kept[BACKUP_COUNT - 1],
+            "settings.json.20260103T000000000Z.bak"
+        );
+    }
+
+    #[test]
+    fn new_backups_sort_after_older_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        write_backup(&path, "20000101T000000000Z", "1");
+
+        let backup = new_backup_path(&path).unwrap();
+        fs::write(&backup, "2").unwrap();
+        assert_eq!(list_backups(&path)[0], backup);
+    }
+
+    #[test]
+    fn loads_the_file_when_it_parses() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, "7").unwrap();
+        write_backup(&path, "20260101T000000000Z", "1");
+
+        assert_eq!(load(&path, parse_number), Some(7));
+    }
+
+    #[test]
+    fn falls_back_to_the_newest_readable_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, "not a number").unwrap();
+        write_backup(&path, "20260101T000000000Z", "1");
+        write_backup(&path, "20260201T000000000Z", "2");
+        write_backup(&path, "20260301T000000000Z", "broken");
+
+        assert_eq!(load(&path, parse_number), Some(2));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2");
+        let quarantined = fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|e| e.file_name().to_string_lossy().contains(".corrupt-"))
+            .count();
+        assert_eq!(quarantined, 1);
+    }
+
+    #[test]
+    fn gives_up_without_a_usable_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        assert_eq!(load(&path, parse_number), None);
+
+        fs::write(&path, "broken").unwrap();
+        write_backup(&path, "20260101T000000000Z", "also broken");
+        assert_eq!(load(&path, parse_number), None);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "broken");
+    }
+}
@@ -0,0 +1,264 @@
+use crate::db;
+use crate::error::NidaleeError;
+use crate::vault::VaultKey;
+#[cfg(test)]
+use crate::vault::{self, KdfParams};
+use crate::{Account, Settings};
+#[cfg(test)]
+use chrono::Utc;
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+pub trait AccountStore {
+    fn is_password_protected(&self) -> Result<bool, NidaleeError>;
+    // Binds a keyfile key to the store, creating the vault on first use.
+    fn attach_key(&mut self, key: &VaultKey) -> Result<(), NidaleeError>;
+    fn unlock(&mut self, password: &str) -> Result<VaultKey, NidaleeError>;
+    fn rekey(&mut self, old_key: &VaultKey, new_key: &VaultKey) -> Result<(), NidaleeError>;
+
+    fn load_accounts(&self, key: &VaultKey) -> Result<HashMap<String, Account>, NidaleeError>;
+    fn save_account(&mut self, key: &VaultKey, account: &Account) -> Result<(), NidaleeError>;
+    fn delete_account(&mut self, id: &str) -> Result<(), NidaleeError>;
+    // Returns the launch timestamp written to last_login.
+    fn record_launch(
+        &mut self,
+        account_id: &str,
+        game: &str,
+        success: bool,
+    ) -> Result<String, NidaleeError>;
+
+    fn load_categories(&self) -> Result<Vec<String>, NidaleeError>;
+    fn save_categories(&mut self, categories: &[String]) -> Result<(), NidaleeError>;
+}
+
+pub trait SettingsStore {
+    fn load_settings(&self) -> Result<Option<Settings>, NidaleeError>;
+    fn save_settings(&mut self, settings: &Settings) -> Result<(), NidaleeError>;
+}
+
+pub trait Store: AccountStore + SettingsStore + Send {}
+
+impl<T: AccountStore + SettingsStore + Send> Store for T {}
+
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    pub fn new(conn: Connection) -> Self {
+        SqliteStore { conn }
+    }
+}
+
+impl AccountStore for SqliteStore {
+    fn is_password_protected(&self) -> Result<bool, NidaleeError> {
+        Ok(db::vault_record(&self.conn)?
+            .map(|record| record.kdf.is_some())
+            .unwrap_or(false))
+    }
+
+    fn attach_key(&mut self, key: &VaultKey) -> Result<(), NidaleeError> {
+        match db::vault_record(&self.conn)? {
+            Some(record) if !db::verify_key(&record, key) => Err(NidaleeError::VaultUnavailable(
+                "Vault key does not match the stored vault".to_string(),
+            )),
+            Some(_) => Ok(()),
+            None => db::init_vault(&self.conn, key),
+        }
+    }
+
+    fn unlock(&mut self, password: &str) -> Result<VaultKey, NidaleeError> {
+        db::unlock_with_password(&self.conn, password)
+    }
+
+    fn rekey(&mut self, old_key: &VaultKey, new_key: &VaultKey) -> Result<(), NidaleeError> {
+        db::rekey(&self.conn, old_key, new_key)
+    }
+
+    fn load_accounts(&self, key: &VaultKey) -> Result<HashMap<String, Account>, NidaleeError> {
+        db::load_accounts(&self.conn, key)
+    }
+
+    fn save_account(&mut self, key: &VaultKey, account: &Account) -> Result<(), NidaleeError> {
+        db::save_account(&self.conn, key, account)
+    }
+
+    fn delete_account(&mut self, id: &str) -> Result<(), NidaleeError> {
+        db::delete_account(&self.conn, id)
+    }
+
+    fn record_launch(
+        &mut self,
+        account_id: &str,
+        game: &str,
+        success: bool,
+    ) -> Result<String, NidaleeError> {
+        db::record_launch(&self.conn, account_id, game, success)
+    }
+
+    fn load_categories(&self) -> Result<Vec<String>, NidaleeError> {
+        db::load_categories(&self.conn)
+    }
+
+    fn save_categories(&mut self, categories: &[String]) -> Result<(), NidaleeError> {
+        db::save_categories(&self.conn, categories)
+    }
+}
+
+impl SettingsStore for SqliteStore {
+    fn load_settings(&self) -> Result<Option<Settings>, NidaleeError> {
+        db::load_settings(&self.conn)
+    }
+
+    fn save_settings(&mut self, settings: &Settings) -> Result<(), NidaleeError> {
+        db::save_settings(&self.conn, settings)
+    }
+}
+
+#[cfg(test)]
+struct MemoryVault {
+    kdf: Option<KdfParams>,
+    check_value: Vec<u8>,
+}
+
+// Keeps everything in process memory with the same rules as SqliteStore, so
+// command logic can be tested without an app data directory.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryStore {
+    vault: Option<MemoryVault>,
+    accounts: HashMap<String, Account>,
+    categories: Vec<String>,
+    settings: Option<Settings>,
+}
+
+#[cfg(test)]
+impl MemoryStore {
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+
+    fn set_vault(&mut self, key: &VaultKey) -> Result<(), NidaleeError> {
+        self.vault = Some(MemoryVault {
+            kdf: key.kdf().cloned(),
+            check_value: vault::check_value(key)?,
+        });
+        Ok(())
+    }
+
+    fn verify(&self, key: &VaultKey) -> Result<(), NidaleeError> {
+        match &self.vault {
+            Some(stored) if vault::verify_check(key, &stored.check_value) => Ok(()),
+            Some(_) => Err(NidaleeError::VaultUnavailable(
+                "Vault key does not match the stored vault".to_string(),
+            )),
+            None => Err(NidaleeError::Internal(
+                "Vault has not been created".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+impl AccountStore for MemoryStore {
+    fn is_password_protected(&self) -> Result<bool, NidaleeError> {
+        Ok(self
+            .vault
+            .as_ref()
+            .map(|stored| stored.kdf.is_some())
+            .unwrap_or(false))
+    }
+
+    fn attach_key(&mut self, key: &VaultKey) -> Result<(), NidaleeError> {
+        match self.vault {
+            Some(_) => self.verify(key),
+            None => self.set_vault(key),
+        }
+    }
+
+    fn unlock(&mut self, password: &str) -> Result<VaultKey, NidaleeError> {
+        let params = self
+            .vault
+            .as_ref()
+            .and_then(|stored| stored.kdf.as_ref())
+            .ok_or_else(|| NidaleeError::Internal("Vault is not password protected".to_string()))?;
+        let key = vault::derive_key(password, params)?;
+        self.verify(&key).map_err(|_| NidaleeError::WrongPassword)?;
+        Ok(key)
+    }
+
+    fn rekey(&mut self, old_key: &VaultKey, new_key: &VaultKey) -> Result<(), NidaleeError> {
+        self.verify(old_key)?;
+        self.set_vault(new_key)
+    }
+
+    fn load_accounts(&self, key: &VaultKey) -> Result<HashMap<String, Account>, NidaleeError> {
+        self.verify(key)?;
+        Ok(self.accounts.clone())
+    }
+
+    fn save_account(&mut self, key: &VaultKey, account: &Account) -> Result<(), NidaleeError> {
+        self.verify(key)?;
+        let mut account = account.clone();
+        account.category = account.category.trim().to_string();
+        if !account.category.is_empty() && !self.categories.contains(&account.category) {
+            self.categories.push(account.category.clone());
+        }
+        self.accounts.insert(account.id.clone(), account);
+        Ok(())
+    }
+
+    fn delete_account(&mut self, id: &str) -> Result<(), NidaleeError> {
+        self.accounts.remove(id);
+        Ok(())
+    }
+
+    fn record_launch(
+        &mut self,
+        account_id: &str,
+        _game: &str,
+        success: bool,
+    ) -> Result<String, NidaleeError> {
+        let now = Utc::now().to_rfc3339();
+        if success {
+            if let Some(account) = self.accounts.get_mut(account_id) {
+                account.last_login = Some(now.clone());
+                account.login_count += 1;
+            }
+        }
+        Ok(now)
+    }
+
+    fn load_categories(&self) -> Result<Vec<String>, NidaleeError> {
+        Ok(self.categories.clone())
+    }
+
+    fn save_categories(&mut self, categories: &[String]) -> Result<(), NidaleeError> {
+        let mut kept: Vec<String> = Vec::new();
+        for name in categories {
+            let name = name.trim().to_string();
+            if !name.is_empty() && !kept.contains(&name) {
+                kept.push(name);
+            }
+        }
+        for account in self.accounts.values_mut() {
+            if !kept.contains(&account.category) {
+                account.category.clear();
+            }
+        }
+        self.categories = kept;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl SettingsStore for MemoryStore {
+    fn load_settings(&self) -> Result<Option<Settings>, NidaleeError> {
+        Ok(self.settings.clone())
+    }
+
+    fn save_settings(&mut self, settings: &Settings) -> Result<(), NidaleeError> {
+        self.settings = Some(settings.clone());
+        Ok(())
+    }
+}
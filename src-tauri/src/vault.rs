@@ -0,0 +1,363 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use crate::error::NidaleeError;
+use crate::storage;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroize;
+
+pub const VAULT_FORMAT: &str = "nidalee-vault";
+pub const VAULT_VERSION: u32 = 2;
+pub const WRONG_PASSWORD: &str = "Failed to decrypt vault. Check your master password.";
+
+const CHECK_PLAINTEXT: &[u8] = b"nidalee-vault-check";
+const CHECK_AAD: &[u8] = b"vault";
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+const KDF_ALGORITHM: &str = "argon2id";
+const KDF_MEMORY_KIB: u32 = 64 * 1024;
+const KDF_ITERATIONS: u32 = 3;
+const KDF_PARALLELISM: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KdfParams {
+    pub algorithm: String,
+    pub salt: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VaultFile {
+    format: String,
+    version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<KdfParams>,
+    nonce: String,
+    ciphertext: String,
+}
+
+pub struct VaultKey {
+    bytes: [u8; KEY_LEN],
+    kdf: Option<KdfParams>,
+}
+
+impl VaultKey {
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; KEY_LEN];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        VaultKey { bytes, kdf: None }
+    }
+
+    pub fn is_password_protected(&self) -> bool {
+        self.kdf.is_some()
+    }
+
+    pub fn kdf(&self) -> Option<&KdfParams> {
+        self.kdf.as_ref()
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.bytes))
+    }
+}
+
+impl Drop for VaultKey {
+    fn drop(&mut self) {
+        self.bytes.zeroize();
+    }
+}
+
+pub fn load_or_create_key(path: &Path) -> Result<VaultKey, NidaleeError> {
+    // Only a missing file gets a new key; one that can't be read is never replaced.
+    match fs::read_to_string(path) {
+        Ok(content) => {
+            let bytes = STANDARD
+                .decode(content.trim())
+                .map_err(|e| NidaleeError::Parse(format!("Invalid vault key file: {}", e)))?;
+            let bytes: [u8; KEY_LEN] = bytes
+                .try_into()
+                .map_err(|_| NidaleeError::Parse("Invalid vault key length".to_string()))?;
+            return Ok(VaultKey { bytes, kdf: None });
+        }
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(NidaleeError::Io(format!("Failed to read vault key: {}", e)));
+        }
+        Err(_) => {}
+    }
+
+    let key = VaultKey::generate();
+    storage::write_atomic(path, STANDARD.encode(key.bytes).as_bytes())
+        .map_err(|e| NidaleeError::Io(format!("Failed to write vault key: {}", e)))?;
+    Ok(key)
+}
+
+pub fn new_password_key(password: &str) -> Result<VaultKey, NidaleeError> {
+    if password.is_empty() {
+        return Err(NidaleeError::InvalidInput(
+            "Master password cannot be empty".to_string(),
+        ));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+
+    derive_key(
+        password,
+        &KdfParams {
+            algorithm: KDF_ALGORITHM.to_string(),
+            salt: STANDARD.encode(salt),
+            memory_kib: KDF_MEMORY_KIB,
+            iterations: KDF_ITERATIONS,
+            parallelism: KDF_PARALLELISM,
+        },
+    )
+}
+
+pub fn derive_key(password: &str, params: &KdfParams) -> Result<VaultKey, NidaleeError> {
+    if params.algorithm != KDF_ALGORITHM {
+        return Err(NidaleeError::Parse(format!(
+            "Unsupported key derivation: {}",
+            params.algorithm
+        )));
+    }
+
+    let salt = STANDARD.decode(&params.salt).map_err(base64_err)?;
+    let argon_params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        Some(KEY_LEN),
+    )
+    .map_err(|e| NidaleeError::Parse(e.to_string()))?;
+
+    let mut bytes = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params)
+        .hash_password_into(password.as_bytes(), &salt, &mut bytes)
+        .map_err(|e| NidaleeError::Internal(e.to_string()))?;
+
+    Ok(VaultKey {
+        bytes,
+        kdf: Some(params.clone()),
+    })
+}
+
+pub fn kdf_params(contents: &str) -> Result<Option<KdfParams>, NidaleeError> {
+    let file: VaultFile = serde_json::from_str(contents)?;
+    Ok(file.kdf)
+}
+
+fn base64_err(e: base64::DecodeError) -> NidaleeError {
+    NidaleeError::Parse(e.to_string())
+}
+
+fn header_aad(version: u32) -> Vec<u8> {
+    format!("{}:{}", VAULT_FORMAT, version).into_bytes()
+}
+
+pub fn is_vault(contents: &str) -> bool {
+    serde_json::from_str::<VaultFile>(contents)
+        .map(|file| file.format == VAULT_FORMAT)
+        .unwrap_or(false)
+}
+
+pub fn encrypt(key: &VaultKey, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, NidaleeError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let ciphertext = key
+        .cipher()
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| NidaleeError::Internal("Failed to encrypt vault".to_string()))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+pub fn decrypt(key: &VaultKey, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, NidaleeError> {
+    if sealed.len() < NONCE_LEN {
+        return Err(NidaleeError::Parse("Invalid vault nonce".to_string()));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+
+    key.cipher()
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| NidaleeError::WrongPassword)
+}
+
+pub fn check_value(key: &VaultKey) -> Result<Vec<u8>, NidaleeError> {
+    encrypt(key, CHECK_PLAINTEXT, CHECK_AAD)
+}
+
+pub fn verify_check(key: &VaultKey, check_value: &[u8]) -> bool {
+    decrypt(key, check_value, CHECK_AAD)
+        .map(|check| check == CHECK_PLAINTEXT)
+        .unwrap_or(false)
+}
+
+pub fn open(key: &VaultKey, contents: &str) -> Result<Vec<u8>, NidaleeError> {
+    let file: VaultFile = serde_json::from_str(contents)?;
+    if file.format != VAULT_FORMAT {
+        return Err(NidaleeError::Parse("Not a Nidalee vault file".to_string()));
+    }
+    if file.version == 0 || file.version > VAULT_VERSION {
+        return Err(NidaleeError::Parse(format!(
+            "Unsupported vault version: {}",
+            file.version
+        )));
+    }
+    if file.kdf != key.kdf {
+        return Err(NidaleeError::VaultUnavailable(
+            "Vault key does not match this vault".to_string(),
+        ));
+    }
+
+    let nonce = STANDARD.decode(&file.nonce).map_err(base64_err)?;
+    if nonce.len() != NONCE_LEN {
+        return Err(NidaleeError::Parse("Invalid vault nonce".to_string()));
+    }
+    let mut sealed = nonce;
+    sealed.extend(STANDARD.decode(&file.ciphertext).map_err(base64_err)?);
+
+    decrypt(key, &sealed, &header_aad(file.version))
+}
+
+// Writes a vault file the way earlier releases did, for import tests. The header
+// can claim a different version than the one bound into the AAD.
+#[cfg(test)]
+pub fn seal_file(key: &VaultKey, version: u32, aad_version: u32, plaintext: &[u8]) -> String {
+    let sealed = encrypt(key, plaintext, &header_aad(aad_version)).unwrap();
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    serde_json::to_string(&VaultFile {
+        format: VAULT_FORMAT.to_string(),
+        version,
+        kdf: key.kdf.clone(),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypts_and_decrypts() {
+        let key = VaultKey::generate();
+        let sealed = encrypt(&key, b"accounts", b"aad").unwrap();
+        assert_ne!(&sealed[NONCE_LEN..], b"accounts");
+        assert_eq!(decrypt(&key, &sealed, b"aad").unwrap(), b"accounts");
+
+        let other = VaultKey::generate();
+        assert_eq!(
+            decrypt(&other, &sealed, b"aad"),
+            Err(NidaleeError::WrongPassword)
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_ciphertext_and_tag() {
+        let key = VaultKey::generate();
+        let sealed = encrypt(&key, b"accounts", b"aad").unwrap();
+
+        // The first byte after the nonce is ciphertext, the last is the GCM tag.
+        for index in [NONCE_LEN, sealed.len() - 1] {
+            let mut tampered = sealed.clone();
+            tampered[index] ^= 0x01;
+            assert_eq!(
+                decrypt(&key, &tampered, b"aad"),
+                Err(NidaleeError::WrongPassword)
+            );
+        }
+        assert_eq!(
+            decrypt(&key, &sealed, b"other"),
+            Err(NidaleeError::WrongPassword)
+        );
+    }
+
+    #[test]
+    fn opens_only_a_matching_header() {
+        let key = VaultKey::generate();
+        let file = seal_file(&key, VAULT_VERSION, VAULT_VERSION, b"accounts");
+        assert!(is_vault(&file));
+        assert_eq!(open(&key, &file).unwrap(), b"accounts");
+
+        // The version is bound into the AAD, so rewriting it breaks the tag.
+        let relabelled = seal_file(&key, 1, VAULT_VERSION, b"accounts");
+        assert_eq!(open(&key, &relabelled), Err(NidaleeError::WrongPassword));
+
+        let newer = seal_file(&key, VAULT_VERSION + 1, VAULT_VERSION + 1, b"accounts");
+        assert_eq!(open(&key, &newer).unwrap_err().code(), "parse");
+
+        let foreign = file.replace(VAULT_FORMAT, "other-vault");
+        assert!(!is_vault(&foreign));
+        assert_eq!(open(&key, &foreign).unwrap_err().code(), "parse");
+    }
+
+    #[test]
+    fn refuses_a_key_from_another_vault() {
+        let key = VaultKey::generate();
+        let file = seal_file(&key, VAULT_VERSION, VAULT_VERSION, b"accounts");
+        let password_key = VaultKey {
+            bytes: key.bytes,
+            kdf: Some(KdfParams {
+                algorithm: KDF_ALGORITHM.to_string(),
+                salt: STANDARD.encode([0u8; SALT_LEN]),
+                memory_kib: KDF_MEMORY_KIB,
+                iterations: KDF_ITERATIONS,
+                parallelism: KDF_PARALLELISM,
+            }),
+        };
+        assert_eq!(
+            open(&password_key, &file).err().unwrap(),
+            NidaleeError::VaultUnavailable("Vault key does not match this vault".to_string())
+        );
+    }
+
+    #[test]
+    fn creates_the_key_file_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.key");
+
+        let created = load_or_create_key(&path).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        let loaded = load_or_create_key(&path).unwrap();
+        assert_eq!(created.bytes, loaded.bytes);
+        assert_eq!(fs::read_to_string(&path).unwrap(), written);
+        assert!(!loaded.is_password_protected());
+    }
+
+    #[test]
+    fn never_replaces_an_unreadable_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.key");
+        fs::write(&path, "not a key").unwrap();
+
+        let err = load_or_create_key(&path).err().unwrap();
+        assert_eq!(err.code(), "parse");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "not a key");
+    }
+}
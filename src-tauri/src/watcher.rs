@@ -0,0 +1,197 @@
+use crate::games::{GameRegistry, RIOT_CLIENT_PROCESSES};
+use crate::platform::ProcessInfo;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetKind {
+    Game,
+    Client,
+}
+
+pub struct Target {
+    pub id: String,
+    pub kind: TargetKind,
+    pub processes: Vec<String>,
+}
+
+// Target id -> PIDs of its running processes. Targets with nothing running are left out.
+pub type Snapshot = BTreeMap<String, Vec<u32>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WatchPayload {
+    pub id: String,
+    pub pids: Vec<u32>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchEvent {
+    pub name: &'static str,
+    pub payload: WatchPayload,
+}
+
+pub fn targets(games: &GameRegistry) -> Vec<Target> {
+    let mut targets: Vec<Target> = games
+        .all()
+        .iter()
+        .map(|game| Target {
+            id: game.id.clone(),
+            kind: TargetKind::Game,
+            processes: game.processes.clone(),
+        })
+        .collect();
+    targets.push(Target {
+        id: "riot_client".to_string(),
+        kind: TargetKind::Client,
+        processes: vec![RIOT_CLIENT_PROCESSES[0].to_string()],
+    });
+    targets
+}
+
+pub fn snapshot(targets: &[Target], running: &[ProcessInfo]) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    for target in targets {
+        let mut pids: Vec<u32> = running
+            .iter()
+            .filter(|process| target.processes.iter().any(|name| process.is_named(name)))
+            .map(|process| process.pid)
+            .collect();
+        if !pids.is_empty() {
+            pids.sort_unstable();
+            snapshot.insert(target.id.clone(), pids);
+        }
+    }
+    snapshot
+}
+
+fn event_name(kind: TargetKind, started: bool) -> &'static str {
+    match (kind, started) {
+        (TargetKind::Game, true) => "game-started",
+        (TargetKind::Game, false) => "game-exited",
+        (TargetKind::Client, true) => "client-started",
+        (TargetKind::Client, false) => "client-exited",
+    }
+}
+
+// Only transitions between "nothing running" and "something running" produce
+// events; PIDs coming and going while a target stays up are not reported.
+pub fn diff(
+    targets: &[Target],
+    previous: &Snapshot,
+    current: &Snapshot,
+    timestamp: &str,
+) -> Vec<WatchEvent> {
+    let mut events = Vec::new();
+    for target in targets {
+        let (started, pids) = match (previous.get(&target.id), current.get(&target.id)) {
+            (None, Some(pids)) => (true, pids),
+            (Some(pids), None) => (false, pids),
+            _ => continue,
+        };
+        events.push(WatchEvent {
+            name: event_name(target.kind, started),
+            payload: WatchPayload {
+                id: target.id.clone(),
+                pids: pids.clone(),
+                timestamp: timestamp.to_string(),
+            },
+        });
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::fake::process;
+
+    const NOW: &str = "2024-05-01T12:00:00+00:00";
+
+    fn snapshot_of(running: &[ProcessInfo]) -> Snapshot {
+        snapshot(&targets(&GameRegistry::builtin()), running)
+    }
+
+    fn event(name: &'static str, id: &str, pids: &[u32]) -> WatchEvent {
+        WatchEvent {
+            name,
+            payload: WatchPayload {
+                id: id.to_string(),
+                pids: pids.to_vec(),
+                timestamp: NOW.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn snapshot_groups_pids_by_target() {
+        let snapshot = snapshot_of(&[
+            process(10, "RiotClientServices.exe", None),
+            process(11, "RiotClientUx.exe", Some(10)),
+            process(21, "LeagueClientUx.exe", Some(20)),
+            process(20, "LeagueClient.exe", Some(10)),
+            process(30, "explorer.exe", None),
+        ]);
+        assert_eq!(
+            snapshot,
+            Snapshot::from([
+                ("league".to_string(), vec![20, 21]),
+                ("riot_client".to_string(), vec![10]),
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_starts() {
+        let targets = targets(&GameRegistry::builtin());
+        let current = snapshot_of(&[
+            process(10, "RiotClientServices.exe", None),
+            process(40, "VALORANT.exe", Some(10)),
+        ]);
+        assert_eq!(
+            diff(&targets, &Snapshot::new(), &current, NOW),
+            [
+                event("game-started", "valorant", &[40]),
+                event("client-started", "riot_client", &[10]),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_exits_with_the_last_pids() {
+        let targets = targets(&GameRegistry::builtin());
+        let previous = snapshot_of(&[
+            process(10, "RiotClientServices.exe", None),
+            process(20, "LeagueClient.exe", Some(10)),
+            process(22, "League of Legends.exe", Some(20)),
+        ]);
+        let current = snapshot_of(&[process(10, "RiotClientServices.exe", None)]);
+        assert_eq!(
+            diff(&targets, &previous, &current, NOW),
+            [event("game-exited", "league", &[20, 22])]
+        );
+
+        assert_eq!(
+            diff(&targets, &current, &Snapshot::new(), NOW),
+            [event("client-exited", "riot_client", &[10])]
+        );
+    }
+
+    #[test]
+    fn steady_state_is_quiet() {
+        let targets = targets(&GameRegistry::builtin());
+        let previous = snapshot_of(&[
+            process(10, "RiotClientServices.exe", None),
+            process(20, "LeagueClient.exe", Some(10)),
+        ]);
+        // The match starts while the League client is already running.
+        let current = snapshot_of(&[
+            process(10, "RiotClientServices.exe", None),
+            process(20, "LeagueClient.exe", Some(10)),
+            process(22, "League of Legends.exe", Some(20)),
+        ]);
+        assert!(diff(&targets, &previous, &previous, NOW).is_empty());
+        assert!(diff(&targets, &previous, &current, NOW).is_empty());
+        assert!(diff(&targets, &Snapshot::new(), &Snapshot::new(), NOW).is_empty());
+    }
+}
@@ -5,13 +5,17 @@ import { MainSection } from './components/MainSection';
 import { AddAccount } from './components/AddAccount';
 import { Statistics } from './components/Statistics';
 import { Settings } from './components/Settings';
+import { UnlockVault } from './components/Vault';
 import { FaGithub } from 'react-icons/fa';
 import { open } from '@tauri-apps/api/shell';
 import { invoke } from '@tauri-apps/api/tauri';
+import { listen } from '@tauri-apps/api/event';
+import { VaultStatus } from './types';
 import { logAppOpen, logInstallation } from './firebase';
 
 const App: React.FC = () => {
   const [activeTab, setActiveTab] = useState<string>('main');
+  const [locked, setLocked] = useState<boolean | null>(null);
 
   useEffect(() => {
     const init = async () => {
@@ -29,6 +33,20 @@ const App: React.FC = () => {
     init();
   }, []);
 
+  useEffect(() => {
+    invoke<VaultStatus>('get_vault_status')
+      .then(status => setLocked(status.locked))
+      .catch(error => {
+        console.error('Failed to get vault status:', error);
+        setLocked(false);
+      });
+
+    const unlisten = listen('vault-locked', () => setLocked(true));
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
   const openGitHub = () => {
     open('https://github.com/dancer/nidalee');
   };
@@ -62,14 +80,22 @@ const App: React.FC = () => {
           </div>
         </div>
 
-        <Tabs activeTab={activeTab} onTabChange={setActiveTab} />
+        {locked ? (
+          <div className="mt-6 bg-bl-dark p-6 rounded-lg border border-bl-gray">
+            <UnlockVault onUnlocked={() => setLocked(false)} />
+          </div>
+        ) : locked === false && (
+          <>
+            <Tabs activeTab={activeTab} onTabChange={setActiveTab} />
 
-        <div className="mt-6 bg-bl-dark p-6 rounded-lg border border-bl-gray">
-          {activeTab === 'main' && <MainSection />}
-          {activeTab === 'add' && <AddAccount />}
-          {activeTab === 'stats' && <Statistics />}
-          {activeTab === 'settings' && <Settings />}
-        </div>
+            <div className="mt-6 bg-bl-dark p-6 rounded-lg border border-bl-gray">
+              {activeTab === 'main' && <MainSection />}
+              {activeTab === 'add' && <AddAccount />}
+              {activeTab === 'stats' && <Statistics />}
+              {activeTab === 'settings' && <Settings />}
+            </div>
+          </>
+        )}
       </div>
     </div>
   );
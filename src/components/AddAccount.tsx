@@ -1,9 +1,9 @@
-import React, { useState } from 'react';
+import React, { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/tauri';
-import { Account } from '../types';
+import { Account, GameDefinition } from '../types';
 import { FormInputEvent, FormSelectEvent } from '../types/events';
 import { logAccountAdd } from '../firebase';
-import { FaUser, FaKey, FaEnvelope, FaGamepad, FaEye, FaEyeSlash, FaPlus } from 'react-icons/fa';
+import { FaUser, FaKey, FaEnvelope, FaGamepad, FaEye, FaEyeSlash, FaPlus, FaCodeBranch } from 'react-icons/fa';
 
 export const AddAccount: React.FC = () => {
   const [formData, setFormData] = useState({
@@ -12,11 +12,19 @@ export const AddAccount: React.FC = () => {
     password: '',
     email: '',
     game_type: '',
+    patchline: '',
   });
 
   const [showPassword, setShowPassword] = useState(false);
   const [currentError, setCurrentError] = useState<string>('');
   const [isLoading, setIsLoading] = useState(false);
+  const [games, setGames] = useState<GameDefinition[]>([]);
+
+  useEffect(() => {
+    invoke<GameDefinition[]>('get_games')
+      .then(setGames)
+      .catch(error => console.error('Failed to load games:', error));
+  }, []);
 
   const validateForm = () => {
     if (!formData.name) {
@@ -50,6 +58,7 @@ export const AddAccount: React.FC = () => {
       const account: Account = {
         id: crypto.randomUUID(),
         ...formData,
+        patchline: formData.patchline.trim() || null,
         category: '',
         last_login: undefined,
       };
@@ -63,6 +72,7 @@ export const AddAccount: React.FC = () => {
         password: '',
         email: '',
         game_type: '',
+        patchline: '',
       });
       setCurrentError('');
 
@@ -192,6 +202,30 @@ export const AddAccount: React.FC = () => {
             </div>
           </div>
 
+          {/* Patchline */}
+          <div>
+            <label className="block text-sm mb-1.5 text-bl-red select-none">
+              Patchline <span className="text-gray-500">(Optional)</span>
+            </label>
+            <div className="relative">
+              <div className="absolute left-3 top-1/2 -translate-y-1/2 text-bl-red">
+                <FaCodeBranch size={15} />
+              </div>
+              <input
+                type="text"
+                name="patchline"
+                value={formData.patchline}
+                onChange={(e: FormInputEvent) => {
+                  setFormData({ ...formData, patchline: e.target.value });
+                }}
+                className="w-full bg-bl-gray border border-bl-light-gray rounded-md pl-10 pr-3 py-2.5 text-base
+                         focus:border-bl-red focus:ring-0 transition-colors"
+                placeholder="live, pbe, ... (defaults to live)"
+                autoComplete="off"
+              />
+            </div>
+          </div>
+
           {/* Game Type */}
           <div>
             <label className="block text-sm mb-1.5 text-bl-red select-none">
@@ -213,8 +247,9 @@ export const AddAccount: React.FC = () => {
                 required
               >
                 <option value="">Select game...</option>
-                <option value="valorant">VALORANT</option>
-                <option value="league">League of Legends</option>
+                {games.map((game) => (
+                  <option key={game.id} value={game.id}>{game.display_name}</option>
+                ))}
                 <option value="both">Both Games</option>
               </select>
             </div>
@@ -1,30 +1,75 @@
 import React, { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/tauri';
-import { Account } from '../types'
+import { listen } from '@tauri-apps/api/event';
+import { Account, GameDefinition, NidaleeError } from '../types'
 import { FormSelectEvent } from '../types/events';
 import { logGameLaunch } from '../firebase';
-import { FaGamepad, FaUser, FaFolder, FaPlay, FaClock, FaExclamationTriangle } from 'react-icons/fa';
+import { FaGamepad, FaUser, FaFolder, FaPlay, FaClock, FaExclamationTriangle, FaTimes } from 'react-icons/fa';
 
-interface GameStatus {
-  league_running: boolean;
-  valorant_running: boolean;
+interface GameState {
+  running: boolean;
 }
 
+type GameStatus = Record<string, GameState>;
+
+interface LaunchProgress {
+  game: string;
+  state: string;
+  attempt: number;
+  message: string | null;
+  percent: number | null;
+}
+
+const GAME_ICONS: Record<string, string> = {
+  league: 'icons/league.png',
+  valorant: 'icons/valorant.png',
+};
+
+const LAUNCH_STEPS: Record<string, string> = {
+  spawning_client: 'Starting Riot Client...',
+  waiting_for_window: 'Waiting for Riot Client...',
+  waiting_for_update: 'Waiting for updates...',
+  signing_out: 'Signing out other account...',
+  logging_in: 'Logging in...',
+  launching_game: 'Launching game...',
+  verifying: 'Verifying...',
+};
+
+const isCancelled = (error: unknown) => (error as NidaleeError)?.code === 'launch_cancelled';
+const errorMessage = (error: unknown) => (error as NidaleeError)?.message;
+
+const isRunning = (status: GameStatus, id: string) => status[id]?.running ?? false;
+const runningGames = (status: GameStatus) => Object.keys(status).filter(id => isRunning(status, id));
+
 export const MainSection: React.FC = () => {
   const [accounts, setAccounts] = useState<Account[]>([]);
   const [selectedCategory, setSelectedCategory] = useState<string>('');
   const [selectedAccount, setSelectedAccount] = useState<string>('');
   const [selectedGame, setSelectedGame] = useState<string>('');
   const [loading, setLoading] = useState(false);
+  const [launchStep, setLaunchStep] = useState<string>('');
   const [categories, setCategories] = useState<string[]>([]);
-  const [gameStatus, setGameStatus] = useState<GameStatus>({ league_running: false, valorant_running: false });
+  const [gameStatus, setGameStatus] = useState<GameStatus>({});
+  const [games, setGames] = useState<GameDefinition[]>([]);
   const [showWarning, setShowWarning] = useState(false);
 
   useEffect(() => {
     loadAccounts();
+    loadGames();
     checkGameStatus();
-    const interval = setInterval(checkGameStatus, 30000);
-    return () => clearInterval(interval);
+    const unlisten = Promise.all([
+      listen('game-started', checkGameStatus),
+      listen('game-exited', checkGameStatus),
+      listen<LaunchProgress>('launch-progress', event => {
+        const { state, attempt, percent } = event.payload;
+        let step = LAUNCH_STEPS[state] ?? '';
+        if (step && percent !== null) step = `${step} ${Math.round(percent)}%`;
+        setLaunchStep(step && attempt > 1 ? `${step} (retry ${attempt - 1})` : step);
+      }),
+    ]);
+    return () => {
+      unlisten.then(fns => fns.forEach(fn => fn()));
+    };
   }, []);
 
   const checkGameStatus = async () => {
@@ -36,6 +81,16 @@ export const MainSection: React.FC = () => {
     }
   };
 
+  const loadGames = async () => {
+    try {
+      setGames(await invoke<GameDefinition[]>('get_games'));
+    } catch (error) {
+      console.error('Failed to load games:', error);
+    }
+  };
+
+  const gameName = (id: string) => games.find(game => game.id === id)?.display_name ?? id;
+
   const loadAccounts = async () => {
     try {
       const accounts = await invoke<Account[]>('get_accounts');
@@ -44,6 +99,9 @@ export const MainSection: React.FC = () => {
       setCategories(uniqueCategories);
     } catch (error) {
       console.error('Failed to load accounts:', error);
+      if ((error as NidaleeError)?.code === 'vault_unavailable') {
+        alert(errorMessage(error));
+      }
     }
   };
 
@@ -52,8 +110,7 @@ export const MainSection: React.FC = () => {
       setLoading(true);
 
       const currentStatus = await invoke<GameStatus>('check_game_status');
-      if ((selectedGame === 'league' && !currentStatus.league_running) ||
-        (selectedGame === 'valorant' && !currentStatus.valorant_running)) {
+      if (!isRunning(currentStatus, selectedGame)) {
         await launchNewGame();
         return;
       }
@@ -65,8 +122,7 @@ export const MainSection: React.FC = () => {
         await new Promise(resolve => setTimeout(resolve, 1000));
         const status = await invoke<GameStatus>('check_game_status');
 
-        if ((selectedGame === 'league' && !status.league_running) ||
-          (selectedGame === 'valorant' && !status.valorant_running)) {
+        if (!isRunning(status, selectedGame)) {
           await launchNewGame();
           return;
         }
@@ -76,7 +132,9 @@ export const MainSection: React.FC = () => {
       throw new Error('Failed to close the game completely after multiple attempts');
     } catch (error) {
       console.error('Error during game switch:', error);
-      alert('Failed to switch accounts. Please close the game manually and try again.');
+      if (!isCancelled(error)) {
+        alert(errorMessage(error) ?? 'Failed to switch accounts. Please close the game manually and try again.');
+      }
     } finally {
       setLoading(false);
       setShowWarning(false);
@@ -85,13 +143,9 @@ export const MainSection: React.FC = () => {
 
   const launchNewGame = async () => {
     try {
-      const account = accounts.find(acc => acc.id === selectedAccount);
-      if (!account) {
-        throw new Error('Selected account not found');
-      }
-
+      setLaunchStep('');
       await invoke('launch_game', {
-        account,
+        accountId: selectedAccount,
         selectedGame
       });
 
@@ -110,7 +164,7 @@ export const MainSection: React.FC = () => {
     }
 
     const currentStatus = await invoke<GameStatus>('check_game_status');
-    if (currentStatus.league_running || currentStatus.valorant_running) {
+    if (runningGames(currentStatus).length > 0) {
       setShowWarning(true);
       return;
     }
@@ -120,12 +174,22 @@ export const MainSection: React.FC = () => {
       await launchNewGame();
     } catch (error) {
       console.error('Failed to launch game:', error);
-      alert('Failed to launch game. Please check your settings.');
+      if (!isCancelled(error)) {
+        alert(errorMessage(error) ?? 'Failed to launch game. Please check your settings.');
+      }
     } finally {
       setLoading(false);
     }
   };
 
+  const handleCancel = async () => {
+    try {
+      await invoke('cancel_launch');
+    } catch (error) {
+      console.error('Failed to cancel launch:', error);
+    }
+  };
+
   return (
     <div className="space-y-4 p-4 max-w-2xl mx-auto">
       {/* Warning Modal */}
@@ -137,9 +201,8 @@ export const MainSection: React.FC = () => {
               <h3 className="text-lg font-medium">Game Already Running</h3>
             </div>
             <p className="text-gray-400 mb-6">
-              {gameStatus.league_running && gameStatus.valorant_running ? 'League of Legends and VALORANT are' :
-                gameStatus.league_running ? 'League of Legends is' : 'VALORANT is'} currently running.
-              Would you like to close {gameStatus.league_running && gameStatus.valorant_running ? 'them' : 'it'} and launch {selectedGame === 'league' ? 'League of Legends' : 'VALORANT'}?
+              {runningGames(gameStatus).map(gameName).join(' and ')} {runningGames(gameStatus).length > 1 ? 'are' : 'is'} currently running.
+              Would you like to close {runningGames(gameStatus).length > 1 ? 'them' : 'it'} and launch {gameName(selectedGame)}?
             </p>
             <div className="flex justify-end gap-4">
               <button
@@ -153,11 +216,8 @@ export const MainSection: React.FC = () => {
                   setShowWarning(false);
                   setLoading(true);
                   try {
-                    if (gameStatus.league_running) {
-                      await invoke('force_close_game', { gameType: 'league' });
-                    }
-                    if (gameStatus.valorant_running) {
-                      await invoke('force_close_game', { gameType: 'valorant' });
+                    for (const id of runningGames(gameStatus)) {
+                      await invoke('force_close_game', { gameType: id });
                     }
                     await new Promise(resolve => setTimeout(resolve, 2000));
                     await handleForceClose();
@@ -241,22 +301,17 @@ export const MainSection: React.FC = () => {
             <span>Game</span>
           </label>
           <div className="flex gap-2">
-            <button
-              onClick={() => setSelectedGame('valorant')}
-              className={`flex-1 bg-bl-gray border ${selectedGame === 'valorant' ? 'border-bl-red' : 'border-bl-light-gray'
-                } rounded-md p-3 hover:border-bl-red transition-colors flex items-center justify-center gap-2`}
-            >
-              <img src="icons/valorant.png" alt="VALORANT" className="w-4 h-4" />
-              <span>Valorant</span>
-            </button>
-            <button
-              onClick={() => setSelectedGame('league')}
-              className={`flex-1 bg-bl-gray border ${selectedGame === 'league' ? 'border-bl-red' : 'border-bl-light-gray'
-                } rounded-md p-3 hover:border-bl-red transition-colors flex items-center justify-center gap-2`}
-            >
-              <img src="icons/league.png" alt="League of Legends" className="w-4 h-4" />
-              <span>League of Legends</span>
-            </button>
+            {games.map((game) => (
+              <button
+                key={game.id}
+                onClick={() => setSelectedGame(game.id)}
+                className={`flex-1 bg-bl-gray border ${selectedGame === game.id ? 'border-bl-red' : 'border-bl-light-gray'
+                  } rounded-md p-3 hover:border-bl-red transition-colors flex items-center justify-center gap-2`}
+              >
+                {GAME_ICONS[game.id] && <img src={GAME_ICONS[game.id]} alt={game.display_name} className="w-4 h-4" />}
+                <span>{game.display_name}</span>
+              </button>
+            ))}
           </div>
         </div>
 
@@ -269,8 +324,18 @@ export const MainSection: React.FC = () => {
                    transition-colors flex items-center justify-center gap-2"
         >
           {loading ? <FaClock size={14} className="animate-spin" /> : <FaPlay size={14} />}
-          <span>{loading ? 'Launching...' : 'Launch Game'}</span>
+          <span>{loading ? launchStep || 'Launching...' : 'Launch Game'}</span>
         </button>
+        {loading && (
+          <button
+            onClick={handleCancel}
+            className="w-full bg-bl-gray border border-bl-light-gray rounded-md p-2 text-sm text-gray-400
+                     hover:border-bl-red hover:text-white transition-colors flex items-center justify-center gap-2"
+          >
+            <FaTimes size={12} />
+            <span>Cancel</span>
+          </button>
+        )}
       </div>
 
       {/* Info Card */}
@@ -1,9 +1,11 @@
 import React, { useEffect, useState } from 'react';
 import { invoke } from '@tauri-apps/api/tauri';
 import { dialog } from '@tauri-apps/api';
-import { Settings as SettingsType } from '../types';
+import { listen } from '@tauri-apps/api/event';
+import { Settings as SettingsType, VaultStatus } from '../types';
 import { FormInputEvent } from '../types/events';
-import { FaCog, FaFolder, FaSave, FaClock, FaWindows, FaMinusSquare, FaGamepad } from 'react-icons/fa';
+import { MasterPassword } from './Vault';
+import { FaCog, FaFolder, FaSave, FaClock, FaWindows, FaMinusSquare, FaGamepad, FaKeyboard, FaMousePointer, FaSearch, FaFileAlt, FaLock } from 'react-icons/fa';
 
 export const Settings: React.FC = () => {
   const [settings, setSettings] = useState<SettingsType>({
@@ -18,11 +20,23 @@ export const Settings: React.FC = () => {
   });
 
   const [isLoading, setIsLoading] = useState(true);
+  const [isScanning, setIsScanning] = useState(false);
+  const [scanStatus, setScanStatus] = useState<string | null>(null);
+  const [vaultStatus, setVaultStatus] = useState<VaultStatus | null>(null);
 
   useEffect(() => {
     loadSettings();
+    loadVaultStatus();
   }, []);
 
+  const loadVaultStatus = async () => {
+    try {
+      setVaultStatus(await invoke<VaultStatus>('get_vault_status'));
+    } catch (error) {
+      console.error('Failed to load vault status:', error);
+    }
+  };
+
   const loadSettings = async () => {
     try {
       const savedSettings = await invoke<SettingsType>('get_settings');
@@ -49,6 +63,31 @@ export const Settings: React.FC = () => {
     }
   };
 
+  const handleRescan = async () => {
+    setIsScanning(true);
+    setScanStatus('Scanning...');
+    const unlisten = await listen<{ root: string }>('install-scan-progress', event => {
+      setScanStatus(`Scanning ${event.payload.root}...`);
+    });
+    try {
+      await invoke('rescan_installs');
+      const saved = await invoke<SettingsType>('get_settings');
+      setSettings(prev => ({
+        ...prev,
+        riot_client_path: saved.riot_client_path,
+        league_path: saved.league_path,
+        valorant_path: saved.valorant_path,
+      }));
+      setScanStatus(saved.riot_client_path ? null : 'Riot Client not found');
+    } catch (error) {
+      console.error('Failed to rescan installs:', error);
+      setScanStatus(null);
+    } finally {
+      unlisten();
+      setIsScanning(false);
+    }
+  };
+
   const handleBrowse = async (setting: keyof SettingsType) => {
     try {
       const selected = await dialog.open({
@@ -115,6 +154,103 @@ export const Settings: React.FC = () => {
                 </p>
               </div>
 
+              {/* Credential Entry */}
+              <div className="space-y-2">
+                <div className="flex items-center gap-2">
+                  <FaKeyboard className="text-bl-red" size={12} />
+                  <label className="text-sm">Credential Entry</label>
+                </div>
+                <div className="flex gap-1">
+                  {([['typing', 'Type'], ['clipboard', 'Paste']] as const).map(([value, label]) => (
+                    <button
+                      key={value}
+                      onClick={() => setSettings(prev => ({ ...prev, credential_entry: value }))}
+                      className={`flex-1 py-2 px-3 text-sm rounded-md transition-colors ${(settings.credential_entry ?? 'typing') === value
+                        ? 'bg-bl-red text-white'
+                        : 'bg-bl-gray border border-bl-light-gray hover:border-bl-red'
+                        }`}
+                    >
+                      {label}
+                    </button>
+                  ))}
+                </div>
+                <p className="text-xs text-gray-400 mt-1">
+                  Paste mode works with any keyboard layout and non-ASCII passwords.
+                  Your clipboard is restored after login.
+                </p>
+              </div>
+
+              {/* Login Field Location */}
+              <div className="space-y-2">
+                <div className="flex items-center gap-2">
+                  <FaMousePointer className="text-bl-red" size={12} />
+                  <label className="text-sm">Login Field Location</label>
+                </div>
+                <div className="flex gap-1">
+                  {([['fixed_offsets', 'Fixed Offsets'], ['profiles', 'Layout Profiles'], ['keyboard', 'Keyboard Only']] as const).map(([value, label]) => (
+                    <button
+                      key={value}
+                      onClick={() => setSettings(prev => ({
+                        ...prev,
+                        field_location: { profiles: [], keyboard_tabs: 0, ...prev.field_location, mode: value },
+                      }))}
+                      className={`flex-1 py-2 px-3 text-sm rounded-md transition-colors ${(settings.field_location?.mode ?? 'fixed_offsets') === value
+                        ? 'bg-bl-red text-white'
+                        : 'bg-bl-gray border border-bl-light-gray hover:border-bl-red'
+                        }`}
+                    >
+                      {label}
+                    </button>
+                  ))}
+                </div>
+                <p className="text-xs text-gray-400 mt-1">
+                  How the username field is found. Try Layout Profiles with display scaling or a resized client,
+                  or Keyboard Only if clicks land in the wrong place.
+                </p>
+              </div>
+
+              {/* Logging */}
+              <div className="space-y-2">
+                <div className="flex items-center gap-2">
+                  <FaFileAlt className="text-bl-red" size={12} />
+                  <label className="text-sm">Log Level</label>
+                </div>
+                <div className="flex gap-1">
+                  {([['error', 'Errors'], ['warn', 'Warnings'], ['info', 'Info'], ['debug', 'Debug']] as const).map(([value, label]) => (
+                    <button
+                      key={value}
+                      onClick={() => setSettings(prev => ({ ...prev, log_level: value }))}
+                      className={`flex-1 py-2 px-3 text-sm rounded-md transition-colors ${(settings.log_level ?? 'info') === value
+                        ? 'bg-bl-red text-white'
+                        : 'bg-bl-gray border border-bl-light-gray hover:border-bl-red'
+                        }`}
+                    >
+                      {label}
+                    </button>
+                  ))}
+                </div>
+                <label className="flex items-center gap-3 group cursor-pointer pt-1">
+                  <div className="relative flex items-center">
+                    <input
+                      type="checkbox"
+                      checked={settings.log_usernames ?? false}
+                      onChange={(e) =>
+                        setSettings(prev => ({ ...prev, log_usernames: e.target.checked }))
+                      }
+                      className="w-4 h-4 border border-bl-light-gray rounded bg-bl-gray
+                               checked:bg-bl-red checked:border-bl-red
+                               focus:ring-1 focus:ring-bl-red focus:ring-offset-0
+                               transition-colors cursor-pointer"
+                    />
+                  </div>
+                  <span className="text-sm">Include usernames in logs</span>
+                </label>
+                <p className="text-xs text-gray-400 mt-1">
+                  Logs are written to the logs folder in the app data directory.
+                  Passwords are never logged.
+                </p>
+              </div>
+
               {/* Checkboxes */}
               <div className="space-y-3">
                 <label className="flex items-center gap-3 group cursor-pointer">
@@ -209,11 +345,32 @@ export const Settings: React.FC = () => {
                     <FaFolder size={12} />
                     Browse
                   </button>
+                  <button
+                    onClick={handleRescan}
+                    disabled={isScanning}
+                    className="px-3 py-1.5 bg-bl-light-gray border border-bl-light-gray rounded-md
+                             hover:border-bl-red transition-colors text-sm flex items-center gap-2"
+                  >
+                    <FaSearch size={12} />
+                    Rescan
+                  </button>
                 </div>
+                {scanStatus && <p className="text-xs text-gray-400 truncate">{scanStatus}</p>}
               </div>
             </div>
           </div>
 
+          {/* Master Password */}
+          {vaultStatus && (
+            <div className="bg-bl-gray border border-bl-light-gray rounded-md">
+              <div className="border-b border-bl-light-gray p-4 flex items-center gap-2">
+                <FaLock className="text-bl-red" size={14} />
+                <h2 className="text-sm font-bold">Master Password</h2>
+              </div>
+              <MasterPassword status={vaultStatus} onChanged={loadVaultStatus} />
+            </div>
+          )}
+
           {/* Save Button */}
           <button
             onClick={handleSave}
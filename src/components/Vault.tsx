@@ -0,0 +1,178 @@
+import React, { useState } from 'react';
+import { invoke } from '@tauri-apps/api/tauri';
+import { NidaleeError, VaultStatus } from '../types';
+import { FormInputEvent } from '../types/events';
+import { FaLock, FaUnlock, FaKey } from 'react-icons/fa';
+
+const errorMessage = (error: unknown) => (error as NidaleeError)?.message ?? String(error);
+
+interface UnlockVaultProps {
+  onUnlocked: () => void;
+}
+
+export const UnlockVault: React.FC<UnlockVaultProps> = ({ onUnlocked }) => {
+  const [password, setPassword] = useState('');
+  const [error, setError] = useState<string>('');
+  const [isLoading, setIsLoading] = useState(false);
+
+  const handleUnlock = async (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!password) {
+      return;
+    }
+
+    setIsLoading(true);
+    try {
+      await invoke('unlock_vault', { password });
+      setPassword('');
+      setError('');
+      onUnlocked();
+    } catch (error) {
+      console.error('Failed to unlock vault:', error);
+      setError(errorMessage(error));
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  return (
+    <form onSubmit={handleUnlock} className="max-w-md mx-auto space-y-4 p-4">
+      <div className="flex items-center gap-2 text-bl-red">
+        <FaLock size={14} />
+        <h2 className="text-sm font-bold">Vault Locked</h2>
+      </div>
+      <p className="text-xs text-gray-400">Enter your master password to unlock your accounts.</p>
+      <input
+        type="password"
+        autoFocus
+        value={password}
+        onChange={(e: FormInputEvent) => setPassword(e.target.value)}
+        placeholder="Master password"
+        className="w-full bg-bl-gray border border-bl-light-gray rounded-md px-3 py-2.5 text-base
+                 focus:border-bl-red focus:ring-0 transition-colors"
+      />
+      {error && <p className="text-xs text-bl-red">{error}</p>}
+      <button
+        type="submit"
+        disabled={isLoading || !password}
+        className="w-full bg-bl-gray border border-bl-light-gray rounded-md p-3 text-base
+                 hover:border-bl-red disabled:opacity-50 disabled:cursor-not-allowed
+                 transition-colors flex items-center justify-center gap-2"
+      >
+        <FaUnlock size={14} />
+        <span>{isLoading ? 'Unlocking...' : 'Unlock'}</span>
+      </button>
+    </form>
+  );
+};
+
+interface MasterPasswordProps {
+  status: VaultStatus;
+  onChanged: () => void;
+}
+
+export const MasterPassword: React.FC<MasterPasswordProps> = ({ status, onChanged }) => {
+  const [currentPassword, setCurrentPassword] = useState('');
+  const [newPassword, setNewPassword] = useState('');
+  const [confirmPassword, setConfirmPassword] = useState('');
+  const [error, setError] = useState<string>('');
+  const [isLoading, setIsLoading] = useState(false);
+
+  const handleChange = async (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!newPassword) {
+      setError('Master password cannot be empty');
+      return;
+    }
+    if (newPassword !== confirmPassword) {
+      setError('Passwords do not match');
+      return;
+    }
+
+    setIsLoading(true);
+    try {
+      await invoke('change_master_password', {
+        currentPassword: status.password_protected ? currentPassword : null,
+        newPassword,
+      });
+      setCurrentPassword('');
+      setNewPassword('');
+      setConfirmPassword('');
+      setError('');
+      onChanged();
+    } catch (error) {
+      console.error('Failed to change master password:', error);
+      setError(errorMessage(error));
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  const handleLock = async () => {
+    try {
+      await invoke('lock_vault');
+    } catch (error) {
+      console.error('Failed to lock vault:', error);
+      setError(errorMessage(error));
+    }
+  };
+
+  const inputClass = `w-full bg-bl-light-gray border border-bl-light-gray rounded-md px-3 py-1.5 text-sm
+                    focus:border-bl-red focus:ring-0 transition-colors`;
+
+  return (
+    <form onSubmit={handleChange} className="p-4 space-y-3">
+      {status.password_protected && (
+        <input
+          type="password"
+          value={currentPassword}
+          onChange={(e: FormInputEvent) => setCurrentPassword(e.target.value)}
+          placeholder="Current master password"
+          className={inputClass}
+        />
+      )}
+      <input
+        type="password"
+        value={newPassword}
+        onChange={(e: FormInputEvent) => setNewPassword(e.target.value)}
+        placeholder="New master password"
+        className={inputClass}
+      />
+      <input
+        type="password"
+        value={confirmPassword}
+        onChange={(e: FormInputEvent) => setConfirmPassword(e.target.value)}
+        placeholder="Confirm new master password"
+        className={inputClass}
+      />
+      {error && <p className="text-xs text-bl-red">{error}</p>}
+      <div className="flex gap-2">
+        <button
+          type="submit"
+          disabled={isLoading}
+          className="flex-1 px-3 py-1.5 bg-bl-light-gray border border-bl-light-gray rounded-md
+                   hover:border-bl-red transition-colors text-sm flex items-center justify-center gap-2"
+        >
+          <FaKey size={12} />
+          {status.password_protected ? 'Change Password' : 'Set Password'}
+        </button>
+        {status.password_protected && (
+          <button
+            type="button"
+            onClick={handleLock}
+            className="px-3 py-1.5 bg-bl-light-gray border border-bl-light-gray rounded-md
+                     hover:border-bl-red transition-colors text-sm flex items-center gap-2"
+          >
+            <FaLock size={12} />
+            Lock Now
+          </button>
+        )}
+      </div>
+      <p className="text-xs text-gray-400">
+        {status.password_protected
+          ? 'Your accounts are encrypted with your master password and lock after inactivity.'
+          : 'Set a master password to encrypt your accounts with it instead of a key file, and to lock the vault.'}
+      </p>
+    </form>
+  );
+};
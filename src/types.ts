@@ -7,6 +7,7 @@ export interface Account {
     category: string;
     last_login: string | undefined;
     game_type: string;
+    patchline?: string | null;
 }
 
 export interface Settings {
@@ -18,9 +19,38 @@ export interface Settings {
     minimize_on_game_launch: boolean;
     login_delay: number;
     window_pos: [number, number] | null;
+    credential_entry?: 'typing' | 'clipboard';
+    field_location?: FieldLocation;
+    log_level?: string;
+    log_usernames?: boolean;
+}
+
+export interface LayoutProfile {
+    name: string;
+    width: number;
+    height: number;
+    username_x: number;
+    username_y: number;
+}
+
+export interface FieldLocation {
+    mode: 'fixed_offsets' | 'profiles' | 'keyboard';
+    profiles: LayoutProfile[];
+    keyboard_tabs: number;
 }
 
 export interface TabItem {
     id: string;
     label: string;
-} 
\ No newline at end of file
+} 
+
+export interface VaultStatus {
+    locked: boolean;
+    password_protected: boolean;
+}
+
+export interface NidaleeError {
+    code: string;
+    message: string;
+    details: Record<string, unknown> | null;
+}
@@ -8,6 +8,12 @@ export interface Account {
     last_login: string | null;
     login_count: number;
     game_type: string;
+    patchline?: string | null;
+}
+
+export interface GameDefinition {
+    id: string;
+    display_name: string;
 }
 
 export interface Settings {
@@ -21,4 +27,15 @@ export interface Settings {
 export interface TabItem {
     id: string;
     label: string;
-} 
\ No newline at end of file
+} 
+
+export interface VaultStatus {
+    locked: boolean;
+    password_protected: boolean;
+}
+
+export interface NidaleeError {
+    code: string;
+    message: string;
+    details: Record<string, unknown> | null;
+}